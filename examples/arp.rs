@@ -52,6 +52,7 @@ fn main() -> ! {
         &mut rx_ring[..],
         &mut tx_ring[..],
         clocks,
+        Default::default(),
         eth_pins,
     )
     .unwrap();
@@ -15,8 +15,8 @@ use stm32_eth::stm32::{interrupt, CorePeripherals, Peripherals, SYST};
 use core::cell::RefCell;
 use cortex_m::interrupt::Mutex;
 
-use smoltcp::iface::{InterfaceBuilder, NeighborCache};
-use smoltcp::socket::{TcpSocket, TcpSocketBuffer};
+use smoltcp::iface::{Config, Interface, SocketSet, SocketStorage};
+use smoltcp::socket::tcp::{Socket as TcpSocket, SocketBuffer as TcpSocketBuffer};
 use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address};
 
@@ -51,6 +51,7 @@ fn main() -> ! {
         &mut rx_ring[..],
         &mut tx_ring[..],
         clocks,
+        Default::default(),
         eth_pins,
     )
     .unwrap();
@@ -58,17 +59,16 @@ fn main() -> ! {
 
     let local_addr = Ipv4Address::new(10, 0, 0, 1);
     let ip_addr = IpCidr::new(IpAddress::from(local_addr), 24);
-    let mut ip_addrs = [ip_addr];
-    let mut neighbor_storage = [None; 16];
-    let neighbor_cache = NeighborCache::new(&mut neighbor_storage[..]);
     let ethernet_addr = EthernetAddress(SRC_MAC);
 
-    let mut sockets: [_; 1] = Default::default();
-    let mut iface = InterfaceBuilder::new(&mut eth_dma, &mut sockets[..])
-        .hardware_addr(ethernet_addr.into())
-        .ip_addrs(&mut ip_addrs[..])
-        .neighbor_cache(neighbor_cache)
-        .finalize();
+    let config = Config::new(ethernet_addr.into());
+    let mut iface = Interface::new(config, &mut eth_dma, Instant::ZERO);
+    iface.update_ip_addrs(|addrs| {
+        addrs.push(ip_addr).ok();
+    });
+
+    let mut socket_storage = [SocketStorage::EMPTY; 1];
+    let mut sockets = SocketSet::new(&mut socket_storage[..]);
 
     let mut server_rx_buffer = [0; 512];
     let mut server_tx_buffer = [0; 512];
@@ -76,7 +76,7 @@ fn main() -> ! {
         TcpSocketBuffer::new(&mut server_rx_buffer[..]),
         TcpSocketBuffer::new(&mut server_tx_buffer[..]),
     );
-    let server_handle = iface.add_socket(server_socket);
+    let server_handle = sockets.add(server_socket);
 
     defmt::info!("Ready, listening at {}", ip_addr);
     loop {
@@ -85,27 +85,23 @@ fn main() -> ! {
             let mut eth_pending = ETH_PENDING.borrow(cs).borrow_mut();
             *eth_pending = false;
         });
-        match iface.poll(Instant::from_millis(time as i64)) {
-            Ok(true) => {
-                let socket = iface.get_socket::<TcpSocket>(server_handle);
-                if !socket.is_open() {
-                    if let Err(e) = socket.listen(80) {
-                        defmt::error!("TCP listen error: {:?}", e)
-                    }
+        if iface.poll(
+            Instant::from_millis(time as i64),
+            &mut eth_dma,
+            &mut sockets,
+        ) {
+            let socket = sockets.get_mut::<TcpSocket>(server_handle);
+            if !socket.is_open() {
+                if let Err(e) = socket.listen(80) {
+                    defmt::error!("TCP listen error: {:?}", e)
                 }
+            }
 
-                if socket.can_send() {
-                    if let Err(e) = socket.send_slice(b"hello\n").map(|_| socket.close()) {
-                        defmt::info!("TCP send error: {:?}", e);
-                    }
+            if socket.can_send() {
+                if let Err(e) = socket.send_slice(b"hello\n").map(|_| socket.close()) {
+                    defmt::info!("TCP send error: {:?}", e);
                 }
             }
-            Ok(false) => {}
-            Err(e) =>
-            // Ignore malformed packets
-            {
-                defmt::info!("Error: {:?}", e);
-            }
         }
     }
 }
@@ -50,6 +50,7 @@ fn main() -> ! {
         &mut rx_ring[..],
         &mut tx_ring[..],
         clocks,
+        Default::default(),
         eth_pins,
     )
     .unwrap();
@@ -57,10 +58,7 @@ fn main() -> ! {
 
     // Main loop
     let mut last_stats_time = 0usize;
-    let mut rx_bytes = 0usize;
-    let mut rx_pkts = 0usize;
-    let mut tx_bytes = 0usize;
-    let mut tx_pkts = 0usize;
+    let mut last_stats = eth_dma.statistics();
     let mut last_link_up = false;
 
     let mut phy = BarePhy::new(eth_mac.with_mii(mdio, mdc), PHY_ADDR, Default::default());
@@ -70,21 +68,19 @@ fn main() -> ! {
 
         // print stats every 30 seconds
         if time >= last_stats_time + 30 {
-            let t = time - last_stats_time;
+            let t = (time - last_stats_time) as u64;
+            let stats = eth_dma.statistics();
             defmt::info!(
                 "T={}\tRx:\t{} KB/s\t{} pps\tTx:\t{} KB/s\t{} pps",
                 time,
-                rx_bytes / 1024 / t,
-                rx_pkts / t,
-                tx_bytes / 1024 / t,
-                tx_pkts / t
+                (stats.rx.bytes - last_stats.rx.bytes) / 1024 / t,
+                (stats.rx.packets - last_stats.rx.packets) / t,
+                (stats.tx.bytes - last_stats.tx.bytes) / 1024 / t,
+                (stats.tx.packets - last_stats.tx.packets) / t
             );
+            defmt::debug!("{}", stats);
 
-            // Reset
-            rx_bytes = 0;
-            rx_pkts = 0;
-            tx_bytes = 0;
-            tx_pkts = 0;
+            last_stats = stats;
             last_stats_time = time;
         }
 
@@ -92,9 +88,14 @@ fn main() -> ! {
         let link_up = phy.phy_link_up();
         if link_up != last_link_up {
             if link_up {
-                defmt::info!("Ethernet: no link detected");
+                match stm32_eth::mac::update_link_from_phy(&mut phy) {
+                    Some((speed, duplex)) => {
+                        defmt::info!("Ethernet: link detected! ({}, {})", speed, duplex)
+                    }
+                    None => defmt::info!("Ethernet: link detected!"),
+                }
             } else {
-                defmt::info!("Ethernet: link detected!");
+                defmt::info!("Ethernet: no link detected");
             }
             last_link_up = link_up;
         }
@@ -108,8 +109,6 @@ fn main() -> ! {
         {
             let mut recvd = 0usize;
             while let Ok(pkt) = eth_dma.recv_next() {
-                rx_bytes += pkt.len();
-                rx_pkts += 1;
                 pkt.free();
 
                 recvd += 1;
@@ -134,10 +133,7 @@ fn main() -> ! {
                 });
 
                 match r {
-                    Ok(()) => {
-                        tx_bytes += SIZE;
-                        tx_pkts += 1;
-                    }
+                    Ok(()) => {}
                     Err(TxError::WouldBlock) => break 'egress,
                 }
             }
@@ -11,7 +11,7 @@ use defmt_rtt as _;
 use panic_probe as _;
 
 use smoltcp::{
-    iface::{self, SocketStorage},
+    iface::SocketStorage,
     wire::{self, IpAddress, Ipv4Address},
 };
 
@@ -23,16 +23,16 @@ const MAC: [u8; 6] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05];
 #[rtic::app(device = stm32_eth::stm32, dispatchers = [SPI1])]
 mod app {
 
-    use crate::common::EthernetPhy;
-
     use systick_monotonic::Systick;
 
-    use stm32_eth::{EthernetDMA, RxRingEntry, TxRingEntry};
+    use stm32_eth::{
+        mac::{phy::Phy, EthernetPhy},
+        EthernetDMA, RxRingEntry, TxRingEntry,
+    };
 
     use smoltcp::{
-        iface::{self, Interface, SocketHandle},
-        socket::TcpSocket,
-        socket::{TcpSocketBuffer, TcpState},
+        iface::{self, Interface, SocketHandle, SocketSet},
+        socket::tcp::{Socket as TcpSocket, SocketBuffer as TcpSocketBuffer, State as TcpState},
         wire::EthernetAddress,
     };
 
@@ -40,7 +40,9 @@ mod app {
 
     #[local]
     struct Local {
-        interface: Interface<'static, &'static mut EthernetDMA<'static, 'static>>,
+        interface: Interface,
+        eth_dma: &'static mut EthernetDMA<'static, 'static>,
+        sockets: SocketSet<'static>,
         tcp_handle: SocketHandle,
     }
 
@@ -84,6 +86,7 @@ mod app {
             rx_ring,
             tx_ring,
             clocks,
+            Default::default(),
             pins,
             mdio,
             mdc,
@@ -98,36 +101,34 @@ mod app {
         defmt::info!("Setting up smoltcp");
         let store = cx.local.storage;
 
-        let mut routes = smoltcp::iface::Routes::new(&mut store.routes_cache[..]);
-        routes
+        let config = iface::Config::new(EthernetAddress::from_bytes(&crate::MAC).into());
+        let mut interface = Interface::new(config, dma, now_fn());
+        interface.update_ip_addrs(|addrs| {
+            addrs.push(crate::IP_ADDRESS).ok();
+        });
+        interface
+            .routes_mut()
             .add_default_ipv4_route(smoltcp::wire::Ipv4Address::UNSPECIFIED)
             .ok();
 
-        let neighbor_cache = smoltcp::iface::NeighborCache::new(&mut store.neighbor_cache[..]);
-
         let rx_buffer = TcpSocketBuffer::new(&mut store.tcp_socket_storage.rx_storage[..]);
         let tx_buffer = TcpSocketBuffer::new(&mut store.tcp_socket_storage.tx_storage[..]);
 
         let socket = TcpSocket::new(rx_buffer, tx_buffer);
 
-        let mut interface = iface::InterfaceBuilder::new(dma, &mut store.sockets[..])
-            .hardware_addr(EthernetAddress::from_bytes(&crate::MAC).into())
-            .neighbor_cache(neighbor_cache)
-            .ip_addrs(&mut store.ip_addrs[..])
-            .routes(routes)
-            .finalize();
-
-        let tcp_handle = interface.add_socket(socket);
+        let mut sockets = SocketSet::new(&mut store.sockets[..]);
+        let tcp_handle = sockets.add(socket);
 
-        let socket = interface.get_socket::<TcpSocket>(tcp_handle);
+        let socket = sockets.get_mut::<TcpSocket>(tcp_handle);
         socket.listen(crate::ADDRESS).ok();
 
-        interface.poll(now_fn()).unwrap();
+        interface.poll(now_fn(), dma, &mut sockets);
 
-        if let Ok(mut phy) = EthernetPhy::from_miim(mac, 0) {
+        if let Ok(mut phy) = EthernetPhy::detect(mac) {
             defmt::info!(
-                "Resetting PHY as an extra step. Type: {}",
-                phy.ident_string()
+                "Resetting PHY as an extra step. Type: {}, address: {}",
+                phy.ident_string(),
+                phy.get_phy_addr()
             );
 
             phy.phy_init();
@@ -141,22 +142,30 @@ mod app {
             Shared {},
             Local {
                 interface,
+                eth_dma: dma,
+                sockets,
                 tcp_handle,
             },
             init::Monotonics(mono),
         )
     }
 
-    #[task(binds = ETH, local = [interface, tcp_handle, data: [u8; 512] = [0u8; 512]], priority = 2)]
+    #[task(binds = ETH, local = [interface, eth_dma, sockets, tcp_handle, data: [u8; 512] = [0u8; 512]], priority = 2)]
     fn eth_interrupt(cx: eth_interrupt::Context) {
-        let (iface, tcp_handle, buffer) = (cx.local.interface, cx.local.tcp_handle, cx.local.data);
-
-        let interrupt_reason = iface.device_mut().interrupt_handler();
+        let (iface, dma, sockets, tcp_handle, buffer) = (
+            cx.local.interface,
+            cx.local.eth_dma,
+            cx.local.sockets,
+            cx.local.tcp_handle,
+            cx.local.data,
+        );
+
+        let interrupt_reason = dma.interrupt_handler();
         defmt::debug!("Got an ethernet interrupt! Reason: {}", interrupt_reason);
 
-        iface.poll(now_fn()).ok();
+        iface.poll(now_fn(), *dma, sockets);
 
-        let socket = iface.get_socket::<TcpSocket>(*tcp_handle);
+        let socket = sockets.get_mut::<TcpSocket>(*tcp_handle);
         if let Ok(recv_bytes) = socket.recv_slice(buffer) {
             if recv_bytes > 0 {
                 socket.send_slice(&buffer[..recv_bytes]).ok();
@@ -170,28 +179,22 @@ mod app {
             defmt::warn!("Disconnected... Reopening listening socket.");
         }
 
-        iface.poll(now_fn()).ok();
+        iface.poll(now_fn(), *dma, sockets);
     }
 }
 
+const IP_ADDRESS: wire::IpCidr =
+    wire::IpCidr::Ipv4(wire::Ipv4Cidr::new(wire::Ipv4Address::new(10, 0, 0, 1), 24));
+
 /// All storage required for networking
 pub struct NetworkStorage {
-    pub ip_addrs: [wire::IpCidr; 1],
-    pub sockets: [iface::SocketStorage<'static>; 1],
+    pub sockets: [SocketStorage<'static>; 1],
     pub tcp_socket_storage: TcpSocketStorage,
-    pub neighbor_cache: [Option<(wire::IpAddress, iface::Neighbor)>; 8],
-    pub routes_cache: [Option<(wire::IpCidr, iface::Route)>; 8],
 }
 
 impl NetworkStorage {
-    const IP_INIT: wire::IpCidr =
-        wire::IpCidr::Ipv4(wire::Ipv4Cidr::new(wire::Ipv4Address::new(10, 0, 0, 1), 24));
-
     pub const fn new() -> Self {
         NetworkStorage {
-            ip_addrs: [Self::IP_INIT],
-            neighbor_cache: [None; 8],
-            routes_cache: [None; 8],
             sockets: [SocketStorage::EMPTY; 1],
             tcp_socket_storage: TcpSocketStorage::new(),
         }
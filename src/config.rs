@@ -0,0 +1,333 @@
+//! Configuration of the ethernet peripheral
+//!
+//! An [`EthernetConfig`] is passed to [`new`](crate::new) and
+//! [`new_with_mii`](crate::new_with_mii) to determine how the MAC and
+//! DMA are set up. The default configuration is suitable for most
+//! applications.
+
+use crate::mac::{Duplex, FlowControl, PauseLowThreshold, Speed, VlanTagFilter};
+
+/// The frames that are passed on to the DMA by the MAC's frame filter.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Pass all received frames, regardless of their address.
+    ReceiveAll,
+    /// Pass all frames, but still evaluate the address filters.
+    Promiscuous,
+    /// Only pass frames that pass the address filters.
+    ///
+    /// Broadcast frames, frames addressed to the station address of the
+    /// MAC and frames that match one of the destination address filters
+    /// are passed. See [`EthernetMAC::set_address_filter`](crate::EthernetMAC::set_address_filter).
+    Filtered,
+}
+
+/// The maximum number of beats that the DMA transfers in
+/// a single burst.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BurstLength {
+    /// 1 beat
+    Beats1,
+    /// 2 beats
+    Beats2,
+    /// 4 beats
+    Beats4,
+    /// 8 beats
+    Beats8,
+    /// 16 beats
+    Beats16,
+    /// 32 beats
+    Beats32,
+}
+
+impl BurstLength {
+    pub(crate) fn bits(&self) -> u8 {
+        match self {
+            BurstLength::Beats1 => 1,
+            BurstLength::Beats2 => 2,
+            BurstLength::Beats4 => 4,
+            BurstLength::Beats8 => 8,
+            BurstLength::Beats16 => 16,
+            BurstLength::Beats32 => 32,
+        }
+    }
+}
+
+/// The arbitration between the RX and TX DMA for access to the bus.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxTxPriority {
+    /// Round-robin with RX:TX priority ratio 1:1
+    Ratio1To1,
+    /// Round-robin with RX:TX priority ratio 2:1
+    Ratio2To1,
+    /// Round-robin with RX:TX priority ratio 3:1
+    Ratio3To1,
+    /// Round-robin with RX:TX priority ratio 4:1
+    Ratio4To1,
+    /// RX always has priority over TX
+    RxFirst,
+}
+
+/// The point at which the transmit FIFO starts sending a frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxThreshold {
+    /// Only start transmitting once a full frame is in the FIFO.
+    StoreAndForward,
+    /// Start transmitting once 16 bytes are in the FIFO.
+    Bytes16,
+    /// Start transmitting once 24 bytes are in the FIFO.
+    Bytes24,
+    /// Start transmitting once 32 bytes are in the FIFO.
+    Bytes32,
+    /// Start transmitting once 40 bytes are in the FIFO.
+    Bytes40,
+    /// Start transmitting once 64 bytes are in the FIFO.
+    Bytes64,
+    /// Start transmitting once 128 bytes are in the FIFO.
+    Bytes128,
+    /// Start transmitting once 192 bytes are in the FIFO.
+    Bytes192,
+    /// Start transmitting once 256 bytes are in the FIFO.
+    Bytes256,
+}
+
+/// The point at which the receive FIFO starts passing a frame
+/// to the DMA.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxThreshold {
+    /// Only pass a frame to the DMA once it has been fully received.
+    StoreAndForward,
+    /// Pass a frame to the DMA once 32 bytes have been received.
+    Bytes32,
+    /// Pass a frame to the DMA once 64 bytes have been received.
+    Bytes64,
+    /// Pass a frame to the DMA once 96 bytes have been received.
+    Bytes96,
+    /// Pass a frame to the DMA once 128 bytes have been received.
+    Bytes128,
+}
+
+/// Configuration of the ethernet MAC and DMA.
+///
+/// The default configuration:
+/// * receives all frames ([`FilterMode::ReceiveAll`]),
+/// * leaves the station address (MAC address) at its reset value,
+/// * disables the VLAN tag filter, and receives both tagged and untagged frames,
+/// * runs at 100 Mbit/s, full duplex,
+/// * uses store-and-forward for both RX and TX,
+/// * uses a DMA burst length of 32 beats, with an RX:TX priority of 2:1,
+/// * enables checksum offloading and CRC stripping,
+/// * disables retransmission after a collision in half-duplex mode,
+/// * disables flow control, and uses a pause time of `0x100` slot times.
+///
+/// ```
+/// use stm32_eth::{config::{BurstLength, FilterMode}, EthernetConfig};
+///
+/// let config = EthernetConfig::default()
+///     .filter_mode(FilterMode::Promiscuous)
+///     .burst_length(BurstLength::Beats8);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthernetConfig {
+    pub(crate) filter_mode: FilterMode,
+    pub(crate) mac_address: Option<[u8; 6]>,
+    pub(crate) vlan_filter: Option<VlanTagFilter>,
+    pub(crate) vlan_only: bool,
+    pub(crate) speed: Speed,
+    pub(crate) duplex: Duplex,
+    pub(crate) burst_length: BurstLength,
+    pub(crate) rx_tx_priority: RxTxPriority,
+    pub(crate) tx_threshold: TxThreshold,
+    pub(crate) rx_threshold: RxThreshold,
+    pub(crate) rx_checksum_offload: bool,
+    pub(crate) tx_checksum_offload: bool,
+    pub(crate) crc_stripping: bool,
+    pub(crate) retry: bool,
+    pub(crate) pause_time: u16,
+    pub(crate) pause_low_threshold: PauseLowThreshold,
+    pub(crate) flow_control: FlowControl,
+}
+
+impl Default for EthernetConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EthernetConfig {
+    /// Create a new [`EthernetConfig`] with the default configuration.
+    pub const fn new() -> Self {
+        Self {
+            filter_mode: FilterMode::ReceiveAll,
+            mac_address: None,
+            vlan_filter: None,
+            vlan_only: false,
+            speed: Speed::Mbps100,
+            duplex: Duplex::Full,
+            burst_length: BurstLength::Beats32,
+            rx_tx_priority: RxTxPriority::Ratio2To1,
+            tx_threshold: TxThreshold::StoreAndForward,
+            rx_threshold: RxThreshold::StoreAndForward,
+            rx_checksum_offload: true,
+            tx_checksum_offload: true,
+            crc_stripping: true,
+            retry: false,
+            pause_time: 0x100,
+            pause_low_threshold: PauseLowThreshold::Minus4,
+            flow_control: FlowControl::NONE,
+        }
+    }
+
+    /// Set the frames that are passed on by the MAC's frame filter.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_filter_mode`](crate::EthernetMAC::set_filter_mode).
+    pub fn filter_mode(mut self, filter_mode: FilterMode) -> Self {
+        self.filter_mode = filter_mode;
+        self
+    }
+
+    /// Set the station address (MAC address) of the MAC.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_mac_address`](crate::EthernetMAC::set_mac_address).
+    pub fn mac_address(mut self, mac_address: [u8; 6]) -> Self {
+        self.mac_address = Some(mac_address);
+        self
+    }
+
+    /// Set the VLAN tag filter of the MAC.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_vlan_filter`](crate::EthernetMAC::set_vlan_filter).
+    pub fn vlan_filter(mut self, vlan_filter: Option<VlanTagFilter>) -> Self {
+        self.vlan_filter = vlan_filter;
+        self
+    }
+
+    /// Only receive frames that the MAC has marked as VLAN frames, and
+    /// drop all other frames.
+    ///
+    /// This can be changed later with [`EthernetDMA::set_vlan_only`](crate::EthernetDMA::set_vlan_only).
+    pub fn vlan_only(mut self, enable: bool) -> Self {
+        self.vlan_only = enable;
+        self
+    }
+
+    /// Set the initial speed of the MAC.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_speed`](crate::EthernetMAC::set_speed).
+    pub fn speed(mut self, speed: Speed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Set the initial duplex mode of the MAC.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_duplex`](crate::EthernetMAC::set_duplex).
+    pub fn duplex(mut self, duplex: Duplex) -> Self {
+        self.duplex = duplex;
+        self
+    }
+
+    /// Set the maximum burst length of both the RX and TX DMA.
+    pub fn burst_length(mut self, burst_length: BurstLength) -> Self {
+        self.burst_length = burst_length;
+        self
+    }
+
+    /// Set the bus arbitration between the RX and TX DMA.
+    pub fn rx_tx_priority(mut self, rx_tx_priority: RxTxPriority) -> Self {
+        self.rx_tx_priority = rx_tx_priority;
+        self
+    }
+
+    /// Set the threshold at which the MAC starts transmitting a frame.
+    ///
+    /// The MAC can only insert checksums if it has the entire frame, so
+    /// [`tx_checksum_offload`](EthernetConfig::tx_checksum_offload) has no
+    /// effect unless the threshold is [`TxThreshold::StoreAndForward`].
+    pub fn tx_threshold(mut self, tx_threshold: TxThreshold) -> Self {
+        self.tx_threshold = tx_threshold;
+        self
+    }
+
+    /// Set the threshold at which received frames are passed to the DMA.
+    pub fn rx_threshold(mut self, rx_threshold: RxThreshold) -> Self {
+        self.rx_threshold = rx_threshold;
+        self
+    }
+
+    /// Enable or disable IPv4 header and TCP/UDP/ICMP payload checksum
+    /// offloading, for both insertion (TX) and verification (RX).
+    ///
+    /// See [`tx_checksum_offload`](EthernetConfig::tx_checksum_offload) for the
+    /// conditions under which checksums are inserted.
+    pub fn checksum_offload(mut self, enable: bool) -> Self {
+        self.rx_checksum_offload = enable;
+        self.tx_checksum_offload = enable;
+        self
+    }
+
+    /// Enable or disable verification of the IPv4 header and TCP/UDP/ICMP
+    /// payload checksums of received frames.
+    ///
+    /// Frames that fail verification are reported as
+    /// [`RxError::DmaError`](crate::RxError::DmaError).
+    pub fn rx_checksum_offload(mut self, enable: bool) -> Self {
+        self.rx_checksum_offload = enable;
+        self
+    }
+
+    /// Enable or disable insertion of the IPv4 header and TCP/UDP/ICMP
+    /// payload checksums into transmitted frames.
+    ///
+    /// Checksums are only inserted if the [`tx_threshold`](EthernetConfig::tx_threshold)
+    /// is [`TxThreshold::StoreAndForward`]. Otherwise, they are computed by the
+    /// network stack, as reported by [`EthernetDMA::tx_checksum_insertion`](crate::EthernetDMA::tx_checksum_insertion).
+    pub fn tx_checksum_offload(mut self, enable: bool) -> Self {
+        self.tx_checksum_offload = enable;
+        self
+    }
+
+    /// Enable or disable automatic stripping of padding and
+    /// the frame check sequence (CRC) from received frames.
+    pub fn crc_stripping(mut self, enable: bool) -> Self {
+        self.crc_stripping = enable;
+        self
+    }
+
+    /// Enable or disable retransmission of frames after a
+    /// collision occurs in half-duplex mode.
+    pub fn retry(mut self, enable: bool) -> Self {
+        self.retry = enable;
+        self
+    }
+
+    /// Set the pause time (in units of 512 bit times) that is sent
+    /// in pause frames.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_pause_time`](crate::EthernetMAC::set_pause_time).
+    pub fn pause_time(mut self, pause_time: u16) -> Self {
+        self.pause_time = pause_time;
+        self
+    }
+
+    /// Set the threshold at which a pause frame is retransmitted.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_pause_low_threshold`](crate::EthernetMAC::set_pause_low_threshold).
+    pub fn pause_low_threshold(mut self, pause_low_threshold: PauseLowThreshold) -> Self {
+        self.pause_low_threshold = pause_low_threshold;
+        self
+    }
+
+    /// Set the initial directions in which the MAC performs flow control.
+    ///
+    /// This can be changed later with [`EthernetMAC::set_flow_control`](crate::EthernetMAC::set_flow_control).
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+}
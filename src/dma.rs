@@ -1,71 +1,297 @@
+use core::ops::Deref;
+
 use cortex_m::peripheral::NVIC;
 
 use crate::{
-    rx::{RxPacket, RxRing},
-    stm32::{Interrupt, ETHERNET_DMA, ETHERNET_MAC},
-    tx::TxRing,
-    RxError, RxRingEntry, TxError, TxRingEntry,
+    config::{RxThreshold, RxTxPriority, TxThreshold},
+    ring::{DmaRegisters, DEFAULT_BUFFER_SIZE},
+    rx::{RxPacket, RxRing, RxStatistics},
+    stm32::{ethernet_dma::RegisterBlock, Interrupt, ETHERNET_DMA, ETHERNET_MAC},
+    tx::{PacketId, TxRing, TxStatistics, TxStatus},
+    EthernetConfig, RxDescriptor, RxError, RxRingEntry, TxDescriptor, TxError, TxRingEntry,
 };
 
-/// Ethernet DMA.
-pub struct EthernetDMA<'rx, 'tx> {
-    eth_dma: ETHERNET_DMA,
-    rx_ring: RxRing<'rx>,
-    tx_ring: TxRing<'tx>,
+#[cfg(feature = "ptp")]
+use crate::ptp::Timestamp;
+
+#[cfg(feature = "async")]
+use {
+    atomic_waker::AtomicWaker,
+    core::{
+        future::poll_fn,
+        task::{Context, Poll},
+    },
+};
+
+/// Woken when a frame has been received, or when the
+/// RX DMA has run out of descriptors.
+#[cfg(feature = "async")]
+static RX_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Woken when a frame has been transmitted, or when
+/// the TX DMA has run out of descriptors.
+#[cfg(feature = "async")]
+static TX_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Transmit interrupt
+const DMASR_TS: u32 = 1 << 0;
+/// Transmit process stopped
+const DMASR_TPSS: u32 = 1 << 1;
+/// Transmit buffer unavailable
+const DMASR_TBUS: u32 = 1 << 2;
+/// Transmit jabber timeout
+const DMASR_TJTS: u32 = 1 << 3;
+/// Receive overflow
+const DMASR_ROS: u32 = 1 << 4;
+/// Transmit underflow
+const DMASR_TUS: u32 = 1 << 5;
+/// Receive interrupt
+const DMASR_RS: u32 = 1 << 6;
+/// Receive buffer unavailable
+const DMASR_RBUS: u32 = 1 << 7;
+/// Receive process stopped
+const DMASR_RPSS: u32 = 1 << 8;
+/// Receive watchdog timeout
+const DMASR_RWTS: u32 = 1 << 9;
+/// Early transmit
+const DMASR_ETS: u32 = 1 << 10;
+/// Fatal bus error
+const DMASR_FBES: u32 = 1 << 13;
+/// Early receive
+const DMASR_ERS: u32 = 1 << 14;
+/// Abnormal interrupt summary
+const DMASR_AIS: u32 = 1 << 15;
+/// Normal interrupt summary
+const DMASR_NIS: u32 = 1 << 16;
+/// Error bits: error during data transfer by the TX DMA (1) or RX DMA (0)
+const DMASR_EBS_TX: u32 = 1 << 23;
+/// Error bits: error during read transfer (1) or write transfer (0)
+const DMASR_EBS_READ: u32 = 1 << 24;
+/// Error bits: error during descriptor access (1) or data buffer access (0)
+const DMASR_EBS_DESCRIPTOR: u32 = 1 << 25;
+
+/// The status bits that are cleared by writing a 1 to them.
+const DMASR_CLEAR_MASK: u32 = DMASR_TS
+    | DMASR_TPSS
+    | DMASR_TBUS
+    | DMASR_TJTS
+    | DMASR_ROS
+    | DMASR_TUS
+    | DMASR_RS
+    | DMASR_RBUS
+    | DMASR_RPSS
+    | DMASR_RWTS
+    | DMASR_ETS
+    | DMASR_FBES
+    | DMASR_ERS
+    | DMASR_AIS
+    | DMASR_NIS;
+
+/// Transmit process stopped interrupt enable
+const DMAIER_TPSIE: u32 = 1 << 1;
+/// Transmit jabber timeout interrupt enable
+const DMAIER_TJTIE: u32 = 1 << 3;
+/// Receive overflow interrupt enable
+const DMAIER_ROIE: u32 = 1 << 4;
+/// Transmit underflow interrupt enable
+const DMAIER_TUIE: u32 = 1 << 5;
+/// Receive process stopped interrupt enable
+const DMAIER_RPSIE: u32 = 1 << 8;
+/// Receive watchdog timeout interrupt enable
+const DMAIER_RWTIE: u32 = 1 << 9;
+/// Fatal bus error interrupt enable
+const DMAIER_FBEIE: u32 = 1 << 13;
+/// Abnormal interrupt summary enable
+const DMAIER_AISE: u32 = 1 << 15;
+
+/// Flush transmit FIFO
+const DMAOMR_FTF: u32 = 1 << 20;
+
+/// `RPS` and `TPS`: Stopped
+const PROCESS_STOPPED: u8 = 0b000;
+
+/// The amount of times that the states of the DMA processes are
+/// polled before stopping them is considered to have timed out.
+const STOP_WAIT_ATTEMPTS: u32 = 100_000;
+/// The amount of times that `DMAOMR.FTF` is polled before flushing
+/// the TX FIFO is considered to have timed out.
+const FLUSH_WAIT_ATTEMPTS: u32 = 100_000;
+/// The amount of times that `DMABMR.SR` is polled before the software
+/// reset of the DMA is considered to have timed out.
+const RESET_WAIT_ATTEMPTS: u32 = 100_000;
+
+/// Access to the registers of the ethernet DMA.
+///
+/// Both the [`RxHalf`] and the [`TxHalf`] hold one of these, so they
+/// can be used independently of each other. To make that sound, each half
+/// only writes to registers (or bits in registers) that belong to its
+/// own direction, and only reads the shared status register. The DMA as a
+/// whole is only reset and configured through an [`EthernetDMA`].
+///
+/// The ethernet DMA peripheral itself is consumed when the [`EthernetDMA`]
+/// is created, and only this module can create a [`DmaRegs`].
+pub(crate) struct DmaRegs {
+    _private: (),
 }
 
-impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
-    /// Create and initialise the ethernet DMA
-    ///
-    /// # Note
-    /// - Make sure that the buffers reside in a memory region that is
-    /// accessible by the peripheral. Core-Coupled Memory (CCM) is
-    /// usually not accessible.
-    pub(crate) fn new(
-        eth_dma: ETHERNET_DMA,
-        // Take a reference to ETHERNET_MAC to ensure that
-        // this function cannot be called before `EthernetMAC::new`.
-        // If we do that, shenanigans ensues (presumably clock and wait
-        // condition mismatch)
-        #[allow(unused_variables)] eth_mac: &ETHERNET_MAC,
-        rx_buffer: &'rx mut [RxRingEntry],
-        tx_buffer: &'tx mut [TxRingEntry],
-    ) -> Self {
-        // reset DMA bus mode register
-        eth_dma.dmabmr.modify(|_, w| w.sr().set_bit());
+impl DmaRegs {
+    fn new() -> Self {
+        Self { _private: () }
+    }
+}
 
-        // Wait until done
-        while eth_dma.dmabmr.read().sr().bit_is_set() {}
+impl Deref for DmaRegs {
+    type Target = RegisterBlock;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*ETHERNET_DMA::ptr() }
+    }
+}
+
+impl DmaRegisters for DmaRegs {
+    fn set_rx_descriptor_list(&self, address: *const RxDescriptor) {
+        self.dmardlar
+            .write(|w| unsafe { w.srl().bits(address as u32) });
+    }
 
+    fn start_rx(&self) {
+        self.dmaomr.modify(|_, w| w.sr().set_bit());
+    }
+
+    fn demand_rx_poll(&self) {
+        self.dmarpdr.write(|w| unsafe { w.rpd().bits(1) });
+    }
+
+    fn rx_process_state(&self) -> u8 {
+        self.dmasr.read().rps().bits()
+    }
+
+    fn read_missed_frames(&self) -> u32 {
+        self.dmamfbocr.read().bits()
+    }
+
+    fn set_tx_descriptor_list(&self, address: *const TxDescriptor) {
+        self.dmatdlar
+            // Note: unsafe block required for `stm32f107`.
+            .write(|w| unsafe { w.stl().bits(address as u32) });
+    }
+
+    fn start_tx(&self) {
+        self.dmaomr.modify(|_, w| w.st().set_bit());
+    }
+
+    fn demand_tx_poll(&self) {
+        self.dmatpdr.write(|w| {
+            #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+            {
+                w.tpd().poll()
+            }
+            #[cfg(feature = "stm32f1xx-hal")]
+            unsafe {
+                // TODO: There is no nice `poll` method for `stm32f107`?
+                w.tpd().bits(0)
+            }
+        });
+    }
+
+    fn tx_process_state(&self) -> u8 {
+        self.dmasr.read().tps().bits()
+    }
+
+    fn stop_rx(&self) {
+        self.dmaomr.modify(|_, w| w.sr().clear_bit());
+    }
+
+    fn stop_tx(&self) {
+        self.dmaomr.modify(|_, w| w.st().clear_bit());
+    }
+
+    fn flush_tx_fifo(&self) {
+        self.dmaomr
+            .modify(|r, w| unsafe { w.bits(r.bits() | DMAOMR_FTF) });
+    }
+
+    fn tx_fifo_flushing(&self) -> bool {
+        self.dmaomr.read().bits() & DMAOMR_FTF == DMAOMR_FTF
+    }
+
+    fn software_reset(&self) {
+        self.dmabmr.modify(|_, w| w.sr().set_bit());
+    }
+
+    fn resetting(&self) -> bool {
+        self.dmabmr.read().sr().bit_is_set()
+    }
+
+    fn configure(&self, config: &EthernetConfig) {
         // operation mode register
-        eth_dma.dmaomr.modify(|_, w| {
-            // Dropping of TCP/IP checksum error frames disable
-            w.dtcefd()
-                .set_bit()
-                // Receive store and forward
-                .rsf()
-                .set_bit()
-                // Disable flushing of received frames
-                .dfrf()
-                .set_bit()
-                // Transmit store and forward
-                .tsf()
-                .set_bit()
-                // Forward error frames
-                .fef()
-                .set_bit()
-                // Operate on second frame
-                .osf()
-                .set_bit()
+        self.dmaomr.modify(|_, w| {
+            let rx_store_and_forward = config.rx_threshold == RxThreshold::StoreAndForward;
+            let rtc = match config.rx_threshold {
+                RxThreshold::Bytes64 | RxThreshold::StoreAndForward => 0b00,
+                RxThreshold::Bytes32 => 0b01,
+                RxThreshold::Bytes96 => 0b10,
+                RxThreshold::Bytes128 => 0b11,
+            };
+
+            let tx_store_and_forward = config.tx_threshold == TxThreshold::StoreAndForward;
+            let ttc = match config.tx_threshold {
+                TxThreshold::Bytes64 | TxThreshold::StoreAndForward => 0b000,
+                TxThreshold::Bytes128 => 0b001,
+                TxThreshold::Bytes192 => 0b010,
+                TxThreshold::Bytes256 => 0b011,
+                TxThreshold::Bytes40 => 0b100,
+                TxThreshold::Bytes32 => 0b101,
+                TxThreshold::Bytes24 => 0b110,
+                TxThreshold::Bytes16 => 0b111,
+            };
+
+            unsafe {
+                // Dropping of TCP/IP checksum error frames disable, so
+                // that they are reported as erroneous instead.
+                w.dtcefd()
+                    .set_bit()
+                    // Receive store and forward
+                    .rsf()
+                    .bit(rx_store_and_forward)
+                    // Receive threshold control
+                    .rtc()
+                    .bits(rtc)
+                    // Disable flushing of received frames
+                    .dfrf()
+                    .set_bit()
+                    // Transmit store and forward
+                    .tsf()
+                    .bit(tx_store_and_forward)
+                    // Transmit threshold control
+                    .ttc()
+                    .bits(ttc)
+                    // Forward error frames
+                    .fef()
+                    .set_bit()
+                    // Operate on second frame
+                    .osf()
+                    .set_bit()
+            }
         });
 
         // bus mode register
-        eth_dma.dmabmr.modify(|_, w| {
+        self.dmabmr.modify(|_, w| {
             // For any non-f107 chips, we must use enhanced descriptor format to support checksum
             // offloading and/or timestamps.
             #[cfg(not(feature = "stm32f107"))]
             let w = w.edfe().set_bit();
 
+            let burst_length = config.burst_length.bits();
+
+            let (rx_first, ratio) = match config.rx_tx_priority {
+                RxTxPriority::Ratio1To1 => (false, 0b00),
+                RxTxPriority::Ratio2To1 => (false, 0b01),
+                RxTxPriority::Ratio3To1 => (false, 0b10),
+                RxTxPriority::Ratio4To1 => (false, 0b11),
+                RxTxPriority::RxFirst => (true, 0b00),
+            };
+
             unsafe {
                 // Address-aligned beats
                 w.aab()
@@ -75,29 +301,164 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
                     .set_bit()
                     // Rx DMA PBL
                     .rdp()
-                    .bits(32)
+                    .bits(burst_length)
                     // Programmable burst length
                     .pbl()
-                    .bits(32)
-                    // Rx Tx priority ratio 2:1
+                    .bits(burst_length)
+                    // Rx Tx priority ratio
                     .pm()
-                    .bits(0b01)
+                    .bits(ratio)
+                    // Rx has priority over Tx
+                    .da()
+                    .bit(rx_first)
                     // Use separate PBL
                     .usp()
                     .set_bit()
             }
         });
+    }
+
+    fn interrupt_enable(&self) -> u32 {
+        self.dmaier.read().bits()
+    }
+
+    fn set_interrupt_enable(&self, bits: u32) {
+        self.dmaier.write(|w| unsafe { w.bits(bits) });
+    }
+
+    fn clear_status(&self) {
+        self.dmasr.write(|w| unsafe { w.bits(DMASR_CLEAR_MASK) });
+    }
+}
+
+/// Ethernet DMA.
+///
+/// `N` is the size of the buffers of the RX and TX ring entries.
+///
+/// The DMA can be [split](EthernetDMA::split) into an [`RxHalf`] and a
+/// [`TxHalf`], which can be used independently of each other.
+pub struct EthernetDMA<'rx, 'tx, const N: usize = DEFAULT_BUFFER_SIZE> {
+    rx: RxHalf<'rx, N>,
+    tx: TxHalf<'tx, N>,
+}
+
+impl<'rx, 'tx, const N: usize> EthernetDMA<'rx, 'tx, N> {
+    /// Create and initialise the ethernet DMA
+    ///
+    /// # Note
+    /// - Make sure that the buffers reside in a memory region that is
+    /// accessible by the peripheral. Core-Coupled Memory (CCM) is
+    /// usually not accessible.
+    pub(crate) fn new(
+        eth_dma: ETHERNET_DMA,
+        // Take a reference to ETHERNET_MAC to ensure that
+        // this function cannot be called before `EthernetMAC::new`.
+        // If we do that, shenanigans ensues (presumably clock and wait
+        // condition mismatch)
+        #[allow(unused_variables)] eth_mac: &ETHERNET_MAC,
+        rx_buffer: &'rx mut [RxRingEntry<N>],
+        tx_buffer: &'tx mut [TxRingEntry<N>],
+        config: &EthernetConfig,
+    ) -> Self {
+        // reset DMA bus mode register
+        eth_dma.dmabmr.modify(|_, w| w.sr().set_bit());
 
-        let mut dma = EthernetDMA {
-            eth_dma,
-            rx_ring: RxRing::new(rx_buffer),
-            tx_ring: TxRing::new(tx_buffer),
+        // Wait until done
+        while eth_dma.dmabmr.read().sr().bit_is_set() {}
+
+        let regs = DmaRegs::new();
+        regs.configure(config);
+
+        let mut rx = RxHalf {
+            regs,
+            ring: RxRing::new(rx_buffer, config.rx_checksum_offload),
+            config: *config,
+        };
+
+        // The MAC only inserts checksums in transmit store and forward mode.
+        let tx_checksum_insertion =
+            config.tx_checksum_offload && config.tx_threshold == TxThreshold::StoreAndForward;
+
+        let mut tx = TxHalf {
+            regs: DmaRegs::new(),
+            ring: TxRing::new(tx_buffer, tx_checksum_insertion),
         };
 
-        dma.rx_ring.start(&dma.eth_dma);
-        dma.tx_ring.start(&dma.eth_dma);
+        rx.ring.set_vlan_only(config.vlan_only);
+
+        rx.ring.start(&rx.regs);
+        tx.ring.start(&tx.regs);
+
+        EthernetDMA { rx, tx }
+    }
+
+    /// Borrow the [`RxHalf`] and the [`TxHalf`] of this DMA at the same time.
+    #[cfg(any(
+        feature = "embassy-net",
+        feature = "smoltcp-phy",
+        feature = "smoltcp-phy-0_9"
+    ))]
+    pub(crate) fn halves_mut(&mut self) -> (&mut RxHalf<'rx, N>, &mut TxHalf<'tx, N>) {
+        (&mut self.rx, &mut self.tx)
+    }
+
+    /// Get the statistics that the driver has kept since the DMA
+    /// was created, or since they were last reset.
+    pub fn statistics(&mut self) -> DmaStatistics {
+        DmaStatistics {
+            rx: self.rx.statistics(),
+            tx: self.tx.statistics(),
+        }
+    }
+
+    /// Reset the statistics that the driver keeps.
+    pub fn reset_statistics(&mut self) {
+        self.rx.reset_statistics();
+        self.tx.reset_statistics();
+    }
 
-        dma
+    /// Only receive frames that the MAC has marked as VLAN frames, and
+    /// drop all other frames.
+    ///
+    /// Which frames are marked as VLAN frames is determined by the VLAN tag
+    /// filter of the MAC. See [`EthernetMAC::set_vlan_filter`](crate::EthernetMAC::set_vlan_filter).
+    pub fn set_vlan_only(&mut self, vlan_only: bool) {
+        self.rx.set_vlan_only(vlan_only);
+    }
+
+    /// Whether the IPv4 header and TCP/UDP/ICMP payload checksums
+    /// of received packets are verified in hardware.
+    ///
+    /// See [`EthernetConfig::rx_checksum_offload`](crate::EthernetConfig::rx_checksum_offload).
+    pub fn rx_checksum_offload(&self) -> bool {
+        self.rx.ring.checksum_offload()
+    }
+
+    /// Whether the IPv4 header and TCP/UDP/ICMP payload checksums
+    /// are inserted into transmitted packets in hardware.
+    ///
+    /// This is only the case if [`EthernetConfig::tx_checksum_offload`](crate::EthernetConfig::tx_checksum_offload)
+    /// is enabled and the [`TxThreshold`] is [`TxThreshold::StoreAndForward`].
+    pub fn tx_checksum_insertion(&self) -> bool {
+        self.tx.ring.checksum_insertion()
+    }
+
+    /// Split the DMA into an [`RxHalf`] and a [`TxHalf`].
+    ///
+    /// The halves can be moved to different tasks or interrupt priorities,
+    /// and do not require any locking between each other. They can be
+    /// rejoined with [`EthernetDMA::join`].
+    ///
+    /// Interrupts must be enabled with [`EthernetDMA::enable_interrupt`]
+    /// before splitting the DMA.
+    pub fn split(self) -> (RxHalf<'rx, N>, TxHalf<'tx, N>) {
+        (self.rx, self.tx)
+    }
+
+    /// Join an [`RxHalf`] and a [`TxHalf`] that were created with
+    /// [`EthernetDMA::split`] back together.
+    pub fn join(rx: RxHalf<'rx, N>, tx: TxHalf<'tx, N>) -> Self {
+        Self { rx, tx }
     }
 
     /// Enable RX and TX interrupts
@@ -107,7 +468,7 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     /// clear interrupt pending bits. Otherwise the interrupt will
     /// reoccur immediately.
     pub fn enable_interrupt(&self) {
-        self.eth_dma.dmaier.modify(|_, w| {
+        self.rx.regs.dmaier.modify(|_, w| {
             w
                 // Normal interrupt summary enable
                 .nise()
@@ -120,6 +481,24 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
                 .set_bit()
         });
 
+        // Abnormal interrupts, which are reported in the
+        // `InterruptReasonSummary`. The receive buffer unavailable
+        // interrupt is left disabled, as it fires continuously while
+        // the RX ring is full.
+        self.rx.regs.dmaier.modify(|r, w| unsafe {
+            w.bits(
+                r.bits()
+                    | DMAIER_AISE
+                    | DMAIER_FBEIE
+                    | DMAIER_ROIE
+                    | DMAIER_TUIE
+                    | DMAIER_TJTIE
+                    | DMAIER_RWTIE
+                    | DMAIER_RPSIE
+                    | DMAIER_TPSIE,
+            )
+        });
+
         // Enable ethernet interrupts
         unsafe {
             NVIC::unmask(Interrupt::ETH);
@@ -128,9 +507,35 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
 
     /// Calls [`eth_interrupt_handler()`](fn.eth_interrupt_handler.html)
     pub fn interrupt_handler(&self) -> InterruptReasonSummary {
-        let status = eth_interrupt_handler(&self.eth_dma);
-        eth_interrupt_handler(&self.eth_dma);
-        status
+        handle_interrupt(&self.rx.regs)
+    }
+
+    /// Reset and restart both rings, in order to recover from
+    /// a [fatal bus error](InterruptReasonSummary::fatal_bus_error).
+    ///
+    /// After a fatal bus error, the DMA stops all of its bus accesses. This
+    /// stops both DMA processes and waits until they have stopped, flushes the
+    /// TX FIFO, resets the DMA, and restarts the RX and TX rings from their first
+    /// descriptor. The configuration of the DMA is written again after the reset,
+    /// and the enabled interrupts are retained, but all frames that were
+    /// waiting to be received or transmitted are dropped, and the IDs of
+    /// packets that were sent with [`EthernetDMA::send_with_id`] are forgotten.
+    ///
+    /// A split DMA must be [joined](EthernetDMA::join) before it can be restarted.
+    ///
+    /// Returns [`RestartError::StopTimeout`] if the DMA processes did not stop,
+    /// [`RestartError::FlushTimeout`] if the TX FIFO could not be flushed, which
+    /// happens if the transmit clock from the PHY is missing, and
+    /// [`RestartError::ResetTimeout`] if the reset did not complete. Both DMA
+    /// processes are left stopped in those cases, and restarting can be
+    /// retried later.
+    pub fn reset_and_restart(&mut self) -> Result<(), RestartError> {
+        restart(
+            &self.rx.regs,
+            &mut self.rx.ring,
+            &mut self.tx.ring,
+            &self.rx.config,
+        )
     }
 
     /// Is Rx DMA currently running?
@@ -138,18 +543,26 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     /// It stops if the ring is full. Call `recv_next()` to free an
     /// entry and to demand poll from the hardware.
     pub fn rx_is_running(&self) -> bool {
-        self.rx_ring.running_state(&self.eth_dma).is_running()
+        self.rx.is_running()
     }
 
     /// Receive the next packet (if any is ready), or return `None`
     /// immediately.
-    pub fn recv_next(&mut self) -> Result<RxPacket, RxError> {
-        self.rx_ring.recv_next(&self.eth_dma)
+    pub fn recv_next(&mut self) -> Result<RxPacket<N>, RxError> {
+        self.rx.recv_next()
+    }
+
+    /// Receive the next packet, waiting until one is available.
+    ///
+    /// See [`RxHalf::recv`].
+    #[cfg(feature = "async")]
+    pub async fn recv(&mut self) -> Result<RxPacket<'_, N>, RxError> {
+        self.rx.recv().await
     }
 
     /// Is Tx DMA currently running?
     pub fn tx_is_running(&self) -> bool {
-        self.tx_ring.is_running(&self.eth_dma)
+        self.tx.is_running()
     }
 
     /// Send a packet
@@ -158,10 +571,298 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         length: usize,
         f: F,
     ) -> Result<R, TxError> {
-        let result = self.tx_ring.send(length, f);
-        self.tx_ring.demand_poll(&self.eth_dma);
+        self.tx.send(length, f)
+    }
+
+    /// Send a packet, waiting until an entry in the TX ring is available.
+    ///
+    /// See [`TxHalf::send_async`].
+    #[cfg(feature = "async")]
+    pub async fn send_async<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        length: usize,
+        f: F,
+    ) -> Result<R, TxError> {
+        self.tx.send_async(length, f).await
+    }
+
+    /// Send a packet, and attach `packet_id` to it.
+    ///
+    /// The outcome of the transmission can be polled with [`EthernetDMA::tx_status`].
+    /// If the `ptp` feature is enabled, the timestamp at which the packet
+    /// was transmitted can be retrieved with [`EthernetDMA::tx_timestamp`].
+    pub fn send_with_id<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        length: usize,
+        packet_id: PacketId,
+        f: F,
+    ) -> Result<R, TxError> {
+        self.tx.send_with_id(length, packet_id, f)
+    }
+
+    /// Get the status of the packet that was sent with ID `packet_id`.
+    ///
+    /// Returns `None` if the packet's descriptor has since been
+    /// reused for another packet, or if no packet was sent with
+    /// this ID.
+    pub fn tx_status(&self, packet_id: PacketId) -> Option<TxStatus> {
+        self.tx.status(packet_id)
+    }
+
+    /// Get the timestamp at which the packet that was sent with
+    /// ID `packet_id` was transmitted.
+    ///
+    /// Returns `None` if the packet has not been transmitted yet, or if
+    /// its descriptor has since been reused for another packet.
+    #[cfg(feature = "ptp")]
+    pub fn tx_timestamp(&self, packet_id: PacketId) -> Option<Timestamp> {
+        self.tx.timestamp(packet_id)
+    }
+}
+
+/// Stop, reset and configure the DMA, and restart `rx` and `tx`.
+///
+/// See [`EthernetDMA::reset_and_restart`].
+fn restart<const N: usize>(
+    regs: &impl DmaRegisters,
+    rx: &mut RxRing<N>,
+    tx: &mut TxRing<N>,
+    config: &EthernetConfig,
+) -> Result<(), RestartError> {
+    // Stop both DMA processes, and make sure that they no longer access the
+    // rings before their descriptor lists are changed.
+    regs.stop_rx();
+    regs.stop_tx();
+    let stopped =
+        || regs.rx_process_state() == PROCESS_STOPPED && regs.tx_process_state() == PROCESS_STOPPED;
+    if !(0..STOP_WAIT_ATTEMPTS).any(|_| stopped()) {
+        return Err(RestartError::StopTimeout);
+    }
+
+    // Drop whatever was left in the TX FIFO.
+    regs.flush_tx_fifo();
+    if !(0..FLUSH_WAIT_ATTEMPTS).any(|_| !regs.tx_fifo_flushing()) {
+        return Err(RestartError::FlushTimeout);
+    }
+
+    // The reset clears the enabled interrupts, so they are restored afterwards.
+    let interrupt_enable = regs.interrupt_enable();
+    regs.software_reset();
+    if !(0..RESET_WAIT_ATTEMPTS).any(|_| !regs.resetting()) {
+        return Err(RestartError::ResetTimeout);
+    }
+
+    regs.configure(config);
+    regs.set_interrupt_enable(interrupt_enable);
+
+    // Clear all pending status bits, including the fatal bus error.
+    regs.clear_status();
+
+    rx.start(regs);
+    tx.start(regs);
+
+    Ok(())
+}
+
+/// Statistics that the driver keeps about received and transmitted packets.
+///
+/// See [`EthernetDMA::statistics`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DmaStatistics {
+    /// Statistics about received frames.
+    pub rx: RxStatistics,
+    /// Statistics about transmitted packets.
+    pub tx: TxStatistics,
+}
+
+/// The receiving half of an [`EthernetDMA`].
+///
+/// See [`EthernetDMA::split`].
+pub struct RxHalf<'rx, const N: usize = DEFAULT_BUFFER_SIZE> {
+    regs: DmaRegs,
+    ring: RxRing<'rx, N>,
+    /// The configuration that the DMA was created with, which is kept here so
+    /// that [`EthernetDMA::reset_and_restart`] can still use it after the DMA
+    /// has been split and joined.
+    config: EthernetConfig,
+}
+
+impl<'rx, const N: usize> RxHalf<'rx, N> {
+    /// Is Rx DMA currently running?
+    ///
+    /// It stops if the ring is full. Call `recv_next()` to free an
+    /// entry and to demand poll from the hardware.
+    pub fn is_running(&self) -> bool {
+        self.ring.running_state(&self.regs).is_running()
+    }
+
+    /// Whether a received frame is available, so that
+    /// [`RxHalf::recv_next`] would not return [`RxError::WouldBlock`].
+    pub fn frame_available(&self) -> bool {
+        self.ring.frame_available()
+    }
+
+    /// Get the statistics about received frames.
+    ///
+    /// See [`EthernetDMA::statistics`].
+    pub fn statistics(&mut self) -> RxStatistics {
+        self.ring.statistics(&self.regs)
+    }
+
+    /// Reset the statistics about received frames.
+    pub fn reset_statistics(&mut self) {
+        self.ring.reset_statistics(&self.regs);
+    }
+
+    /// Only receive frames that the MAC has marked as VLAN frames.
+    ///
+    /// See [`EthernetDMA::set_vlan_only`].
+    pub fn set_vlan_only(&mut self, vlan_only: bool) {
+        self.ring.set_vlan_only(vlan_only);
+    }
+
+    /// Receive the next packet (if any is ready), or return `None`
+    /// immediately.
+    pub fn recv_next(&mut self) -> Result<RxPacket<N>, RxError> {
+        self.ring.recv_next(&self.regs)
+    }
+
+    /// Receive the next packet, waiting until one is available.
+    ///
+    /// This relies on the ethernet interrupt to wake the waiting task: interrupts
+    /// must be enabled with [`EthernetDMA::enable_interrupt`], and the interrupt
+    /// handler must call [`eth_interrupt_handler`].
+    #[cfg(feature = "async")]
+    pub async fn recv(&mut self) -> Result<RxPacket<'_, N>, RxError> {
+        poll_fn(|cx| self.poll_frame(cx)).await;
+
+        self.ring.recv_next(&self.regs)
+    }
+
+    /// Poll whether a frame is available, and register
+    /// the waker of `cx` to be woken when one arrives.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_frame(&self, cx: &mut Context) -> Poll<()> {
+        // Register the waker first, so that a frame that
+        // arrives in the meantime is not missed.
+        RX_WAKER.register(cx.waker());
+
+        if self.ring.frame_available() {
+            Poll::Ready(())
+        } else {
+            // Make sure that the DMA has not stopped
+            // while waiting.
+            if !self.ring.running_state(&self.regs).is_running() {
+                self.ring.demand_poll(&self.regs);
+            }
+            Poll::Pending
+        }
+    }
+}
+
+/// The transmitting half of an [`EthernetDMA`].
+///
+/// See [`EthernetDMA::split`].
+pub struct TxHalf<'tx, const N: usize = DEFAULT_BUFFER_SIZE> {
+    regs: DmaRegs,
+    ring: TxRing<'tx, N>,
+}
+
+impl<'tx, const N: usize> TxHalf<'tx, N> {
+    /// Is Tx DMA currently running?
+    pub fn is_running(&self) -> bool {
+        self.ring.is_running(&self.regs)
+    }
+
+    /// Send a packet
+    pub fn send<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        length: usize,
+        f: F,
+    ) -> Result<R, TxError> {
+        let result = self.ring.send(length, None, f);
+        self.ring.demand_poll(&self.regs);
+        result
+    }
+
+    /// Send a packet, and attach `packet_id` to it.
+    ///
+    /// See [`EthernetDMA::send_with_id`].
+    pub fn send_with_id<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        length: usize,
+        packet_id: PacketId,
+        f: F,
+    ) -> Result<R, TxError> {
+        let result = self.ring.send(length, Some(packet_id), f);
+        self.ring.demand_poll(&self.regs);
         result
     }
+
+    /// Get the statistics about transmitted packets.
+    ///
+    /// See [`EthernetDMA::statistics`].
+    pub fn statistics(&self) -> TxStatistics {
+        self.ring.statistics()
+    }
+
+    /// Reset the statistics about transmitted packets.
+    pub fn reset_statistics(&mut self) {
+        self.ring.reset_statistics();
+    }
+
+    /// Whether the next entry in the TX ring is available for sending a packet.
+    pub fn next_entry_available(&self) -> bool {
+        self.ring.next_entry_available()
+    }
+
+    /// Get the status of the packet that was sent with ID `packet_id`.
+    ///
+    /// See [`EthernetDMA::tx_status`].
+    pub fn status(&self, packet_id: PacketId) -> Option<TxStatus> {
+        self.ring.status(packet_id)
+    }
+
+    /// Get the timestamp at which the packet that was sent with
+    /// ID `packet_id` was transmitted.
+    ///
+    /// See [`EthernetDMA::tx_timestamp`].
+    #[cfg(feature = "ptp")]
+    pub fn timestamp(&self, packet_id: PacketId) -> Option<Timestamp> {
+        self.ring.timestamp(packet_id)
+    }
+
+    /// Send a packet, waiting until an entry in the TX ring is available.
+    ///
+    /// This relies on the ethernet interrupt to wake the waiting task: interrupts
+    /// must be enabled with [`EthernetDMA::enable_interrupt`], and the interrupt
+    /// handler must call [`eth_interrupt_handler`].
+    #[cfg(feature = "async")]
+    pub async fn send_async<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        length: usize,
+        f: F,
+    ) -> Result<R, TxError> {
+        poll_fn(|cx| self.poll_entry(cx)).await;
+
+        self.send(length, f)
+    }
+
+    /// Poll whether an entry in the TX ring is available, and register
+    /// the waker of `cx` to be woken when one becomes available.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_entry(&self, cx: &mut Context) -> Poll<()> {
+        // Register the waker first, so that a transmission
+        // that completes in the meantime is not missed.
+        TX_WAKER.register(cx.waker());
+
+        if self.ring.next_entry_available() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
 }
 
 /// A summary of the reasons for the interrupt
@@ -169,9 +870,97 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Clone, Copy)]
 pub struct InterruptReasonSummary {
+    /// A frame was received.
     pub is_rx: bool,
+    /// A frame was transmitted.
     pub is_tx: bool,
+    /// An abnormal interrupt occured. The other fields
+    /// describe its cause(s).
     pub is_error: bool,
+    /// The RX DMA had no descriptor available, and was suspended.
+    pub rx_buffer_unavailable: bool,
+    /// The TX DMA had no packet to transmit, and was suspended.
+    pub tx_buffer_unavailable: bool,
+    /// The RX DMA stopped.
+    pub rx_process_stopped: bool,
+    /// The TX DMA stopped.
+    pub tx_process_stopped: bool,
+    /// A frame longer than 2048 bytes was received.
+    pub rx_watchdog_timeout: bool,
+    /// The transmitter was active for too long.
+    pub tx_jabber_timeout: bool,
+    /// The RX FIFO overflowed.
+    pub rx_overflow: bool,
+    /// The TX FIFO underflowed while a frame was being transmitted.
+    pub tx_underflow: bool,
+    /// A frame that is being transmitted has been fully
+    /// transferred into the TX FIFO.
+    pub early_transmit: bool,
+    /// The first buffer of a frame that is being received
+    /// has been filled.
+    pub early_receive: bool,
+    /// A fatal bus error occured, and the DMA stopped. It must be reset with
+    /// [`EthernetDMA::reset_and_restart`] to continue.
+    pub fatal_bus_error: Option<FatalBusError>,
+}
+
+/// Errors that can occur when restarting the DMA with
+/// [`EthernetDMA::reset_and_restart`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartError {
+    /// The DMA processes did not stop in time.
+    StopTimeout,
+    /// Flushing the TX FIFO did not complete in time.
+    FlushTimeout,
+    /// The software reset of the DMA did not complete in time.
+    ResetTimeout,
+}
+
+/// The cause of a fatal bus error.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatalBusError {
+    /// The error occured in the TX DMA (`true`) or RX DMA (`false`).
+    pub tx: bool,
+    /// The error occured during a read (`true`) or write (`false`) transfer.
+    pub read: bool,
+    /// The error occured while accessing a descriptor (`true`)
+    /// or a data buffer (`false`).
+    pub descriptor: bool,
+}
+
+impl InterruptReasonSummary {
+    fn from_dmasr(dmasr: u32) -> Self {
+        let is_set = |bit: u32| dmasr & bit == bit;
+
+        let fatal_bus_error = if is_set(DMASR_FBES) {
+            Some(FatalBusError {
+                tx: is_set(DMASR_EBS_TX),
+                read: is_set(DMASR_EBS_READ),
+                descriptor: is_set(DMASR_EBS_DESCRIPTOR),
+            })
+        } else {
+            None
+        };
+
+        Self {
+            is_rx: is_set(DMASR_RS),
+            is_tx: is_set(DMASR_TS),
+            is_error: is_set(DMASR_AIS),
+            rx_buffer_unavailable: is_set(DMASR_RBUS),
+            tx_buffer_unavailable: is_set(DMASR_TBUS),
+            rx_process_stopped: is_set(DMASR_RPSS),
+            tx_process_stopped: is_set(DMASR_TPSS),
+            rx_watchdog_timeout: is_set(DMASR_RWTS),
+            tx_jabber_timeout: is_set(DMASR_TJTS),
+            rx_overflow: is_set(DMASR_ROS),
+            tx_underflow: is_set(DMASR_TUS),
+            early_transmit: is_set(DMASR_ETS),
+            early_receive: is_set(DMASR_ERS),
+            fatal_bus_error,
+        }
+    }
 }
 
 /// Call in interrupt handler to clear interrupt reason, when
@@ -182,17 +971,111 @@ pub struct InterruptReasonSummary {
 /// * Via the [`EthernetDMA`](struct.EthernetDMA.html) driver instance that your interrupt handler has access to.
 /// * By unsafely getting `Peripherals`.
 pub fn eth_interrupt_handler(eth_dma: &ETHERNET_DMA) -> InterruptReasonSummary {
-    let status = eth_dma.dmasr.read();
+    handle_interrupt(eth_dma)
+}
 
-    let status = InterruptReasonSummary {
-        is_rx: status.rs().bit_is_set(),
-        is_tx: status.ts().bit_is_set(),
-        is_error: status.ais().bit_is_set(),
-    };
+fn handle_interrupt(eth_dma: &RegisterBlock) -> InterruptReasonSummary {
+    let dmasr = eth_dma.dmasr.read().bits();
+
+    let status = InterruptReasonSummary::from_dmasr(dmasr);
+
+    #[cfg(feature = "async")]
+    {
+        // Tasks that are waiting are woken on errors as well,
+        // so that they can notice that the DMA has stopped.
+        if dmasr & (DMASR_RS | DMASR_RBUS | DMASR_RPSS | DMASR_FBES) != 0 {
+            RX_WAKER.wake();
+        }
+
+        if dmasr & (DMASR_TS | DMASR_TBUS | DMASR_TPSS | DMASR_FBES) != 0 {
+            TX_WAKER.wake();
+        }
+    }
 
-    eth_dma
-        .dmasr
-        .write(|w| w.nis().set_bit().ts().set_bit().rs().set_bit());
+    // Clear all interrupts that were handled, including the abnormal ones so
+    // that they do not fire again. The buffer unavailable flags are cleared
+    // regardless, so that they reflect the latest state of the rings.
+    eth_dma.dmasr.write(|w| unsafe {
+        w.bits((dmasr & DMASR_CLEAR_MASK) | DMASR_NIS | DMASR_TBUS | DMASR_RBUS)
+    });
 
     status
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::BurstLength,
+        ring::sim::{Received, SimDma},
+    };
+
+    const N: usize = 128;
+
+    fn assert_received(ring: &mut RxRing<N>, dma: &SimDma, expected: &[u8]) {
+        let packet = ring.recv_next(dma).expect("a frame should be available");
+
+        let mut buffer = [0; N];
+        let len = packet.copy_into(&mut buffer);
+        assert_eq!(&buffer[..len], expected);
+    }
+
+    fn assert_transmitted(dma: &SimDma, expected: &[u8]) {
+        let mut buffer = [0; N];
+        let len = dma
+            .transmit(&mut buffer)
+            .expect("a packet should be pending");
+        assert_eq!(&buffer[..len], expected);
+    }
+
+    #[test]
+    fn restart_after_fatal_bus_error() {
+        let mut rx_entries = [RxRingEntry::<N>::INIT; 4];
+        let mut tx_entries = [TxRingEntry::<N>::INIT; 4];
+        let mut rx = RxRing::new(&mut rx_entries, false);
+        let mut tx = TxRing::new(&mut tx_entries, false);
+        let config = EthernetConfig::new().burst_length(BurstLength::Beats8);
+        let interrupt_enable = DMAIER_AISE | DMAIER_FBEIE;
+
+        let dma = SimDma::new();
+        dma.configure(&config);
+        dma.set_interrupt_enable(interrupt_enable);
+        rx.start(&dma);
+        tx.start(&dma);
+
+        // Leave a received frame and a packet that is waiting
+        // to be transmitted behind in the rings.
+        assert_eq!(dma.receive(b"received frame"), Received::Complete);
+        assert_received(&mut rx, &dma, b"received frame");
+        assert_eq!(dma.receive(b"dropped frame"), Received::Complete);
+        tx.send(14, None, |buffer| buffer.copy_from_slice(b"dropped packet"))
+            .unwrap();
+
+        dma.fatal_bus_error();
+        assert_eq!(dma.rx_process_state(), PROCESS_STOPPED);
+        assert_eq!(dma.tx_process_state(), PROCESS_STOPPED);
+        assert_eq!(dma.receive(b"missed frame"), Received::Stopped);
+
+        assert_eq!(restart(&dma, &mut rx, &mut tx, &config), Ok(()));
+
+        assert_eq!(dma.resets(), 1);
+        assert!(!dma.fatal_bus_error_pending());
+        assert_eq!(dma.config(), Some(config));
+        assert_eq!(dma.interrupt_enable(), interrupt_enable);
+        assert!(rx.running_state(&dma).is_running());
+        assert!(tx.is_running(&dma));
+
+        // Frames that were left in the rings are dropped.
+        assert_eq!(rx.recv_next(&dma).err(), Some(RxError::WouldBlock));
+        assert_eq!(dma.transmit(&mut [0; N]), None);
+
+        // Both rings work from their first descriptor again.
+        assert_eq!(dma.receive(b"after restart"), Received::Complete);
+        assert_received(&mut rx, &dma, b"after restart");
+
+        tx.send(13, None, |buffer| buffer.copy_from_slice(b"after restart"))
+            .unwrap();
+        tx.demand_poll(&dma);
+        assert_transmitted(&dma, b"after restart");
+    }
+}
@@ -0,0 +1,238 @@
+use core::{
+    marker::PhantomData,
+    ops::DerefMut,
+    sync::atomic::{AtomicBool, Ordering},
+    task::Context,
+};
+
+use atomic_waker::AtomicWaker;
+
+use embassy_net_driver::{
+    Capabilities, Checksum, Driver, HardwareAddress, LinkState, RxToken, TxToken,
+};
+use ieee802_3_miim::phy::Phy;
+
+use crate::{
+    dma::TxHalf,
+    mac::{phy_link_up, update_link_from_phy, FallibleMiim},
+    rx::RxPacket,
+    EthernetDMA, EthernetMAC, DEFAULT_BUFFER_SIZE, MTU,
+};
+
+static LINK_WAKER: AtomicWaker = AtomicWaker::new();
+static LINK_POLL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Request that the [`EmbassyDriver`] polls the link state of the PHY, and
+/// wake the network stack to do so.
+///
+/// Call this periodically (for example once per second from a timer task),
+/// or from the interrupt handler of the PHY, so that link changes are noticed
+/// while no frames are received or sent.
+pub fn request_link_poll() {
+    LINK_POLL_REQUESTED.store(true, Ordering::Release);
+    LINK_WAKER.wake();
+}
+
+/// Use this Ethernet driver with [embassy-net](https://github.com/embassy-rs/embassy).
+///
+/// The driver combines the [`EthernetDMA`] with the PHY, which is used
+/// to determine the link state. Whenever the link comes up, the MAC is
+/// configured for the speed and duplex mode that the PHY has negotiated.
+///
+/// The PHY can not notify the driver of link changes, so its link state is
+/// polled at most once after every time that the network stack receives or
+/// transmits, which happens when it is woken by the ethernet interrupt, and
+/// whenever [`request_link_poll`] is called.
+///
+/// The [`async`](crate::EthernetDMA::recv) support of the DMA is used to wake
+/// the network stack: interrupts must be enabled with [`EthernetDMA::enable_interrupt`],
+/// and the interrupt handler must call [`eth_interrupt_handler`](crate::eth_interrupt_handler).
+pub struct EmbassyDriver<'rx, 'tx, M, P, const N: usize = DEFAULT_BUFFER_SIZE> {
+    dma: EthernetDMA<'rx, 'tx, N>,
+    phy: P,
+    mac_address: [u8; 6],
+    link_up: bool,
+    link_poll_due: bool,
+    _miim: PhantomData<M>,
+}
+
+impl<'rx, 'tx, M, P, const N: usize> EmbassyDriver<'rx, 'tx, M, P, N>
+where
+    M: FallibleMiim + DerefMut<Target = EthernetMAC>,
+    P: Phy<M>,
+{
+    /// Create a new driver from the DMA, and the PHY
+    /// that the MAC is connected to.
+    pub fn new(dma: EthernetDMA<'rx, 'tx, N>, mut phy: P) -> Self {
+        let mac_address = phy.get_miim().mac_address();
+
+        Self {
+            dma,
+            phy,
+            mac_address,
+            link_up: false,
+            // Poll the link state as soon as it is requested
+            link_poll_due: true,
+            _miim: PhantomData,
+        }
+    }
+
+    /// Read the link state from the PHY, and configure the MAC
+    /// if the link has come up.
+    fn poll_link(&mut self) {
+        let link_up = phy_link_up(&mut self.phy);
+
+        self.link_up = if link_up && !self.link_up {
+            update_link_from_phy(&mut self.phy).is_some()
+        } else {
+            link_up
+        };
+    }
+
+    /// Release the DMA and the PHY.
+    pub fn free(self) -> (EthernetDMA<'rx, 'tx, N>, P) {
+        (self.dma, self.phy)
+    }
+}
+
+impl<'rx, 'tx, M, P, const N: usize> Driver for EmbassyDriver<'rx, 'tx, M, P, N>
+where
+    M: FallibleMiim + DerefMut<Target = EthernetMAC>,
+    P: Phy<M>,
+{
+    type RxToken<'a>
+        = EmbassyRxToken<'a, N>
+    where
+        Self: 'a;
+
+    type TxToken<'a>
+        = EmbassyTxToken<'a, 'tx, N>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, cx: &mut Context) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.link_poll_due = true;
+
+        let (rx, tx) = self.dma.halves_mut();
+
+        if rx.poll_frame(cx).is_pending() || tx.poll_entry(cx).is_pending() {
+            return None;
+        }
+
+        match rx.recv_next() {
+            // Frames that span multiple descriptors are copied into a buffer
+            // of `MTU` bytes before they are passed on, so larger frames
+            // cannot be received.
+            Ok(packet) if packet.segment_count() == 1 || packet.frame_len() <= MTU => {
+                Some((EmbassyRxToken { packet }, EmbassyTxToken { tx }))
+            }
+            // The frame could not be received, but more frames may be available.
+            _ => {
+                cx.waker().wake_by_ref();
+                None
+            }
+        }
+    }
+
+    fn transmit(&mut self, cx: &mut Context) -> Option<Self::TxToken<'_>> {
+        self.link_poll_due = true;
+
+        let (_, tx) = self.dma.halves_mut();
+
+        if tx.poll_entry(cx).is_ready() {
+            Some(EmbassyTxToken { tx })
+        } else {
+            None
+        }
+    }
+
+    fn link_state(&mut self, cx: &mut Context) -> LinkState {
+        LINK_WAKER.register(cx.waker());
+
+        let requested = LINK_POLL_REQUESTED.swap(false, Ordering::AcqRel);
+        if self.link_poll_due || requested {
+            self.link_poll_due = false;
+            self.poll_link();
+        }
+
+        if self.link_up {
+            LinkState::Up
+        } else {
+            LinkState::Down
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = Capabilities::default();
+        // Frames that are sent must fit in a single TX buffer
+        caps.max_transmission_unit = N.min(MTU);
+        caps.max_burst_size = Some(1);
+
+        // The MAC verifies and inserts the IPv4 header checksum and the
+        // TCP/UDP/ICMP payload checksums, so the network stack only has
+        // to handle the directions that are not offloaded.
+        let checksum = match (
+            self.dma.rx_checksum_offload(),
+            self.dma.tx_checksum_insertion(),
+        ) {
+            (true, true) => Checksum::None,
+            (true, false) => Checksum::Tx,
+            (false, true) => Checksum::Rx,
+            (false, false) => Checksum::Both,
+        };
+
+        caps.checksum.ipv4 = checksum;
+        caps.checksum.udp = checksum;
+        caps.checksum.tcp = checksum;
+        caps.checksum.icmpv4 = checksum;
+        caps.checksum.icmpv6 = checksum;
+        caps
+    }
+
+    fn hardware_address(&self) -> HardwareAddress {
+        HardwareAddress::Ethernet(self.mac_address)
+    }
+}
+
+/// An embassy-net RX token that can be consumed in order to receive
+/// an ethernet packet.
+pub struct EmbassyRxToken<'a, const N: usize> {
+    packet: RxPacket<'a, N>,
+}
+
+impl<'a, const N: usize> RxToken for EmbassyRxToken<'a, N> {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        if let Some(frame) = self.packet.as_contiguous_mut() {
+            f(frame)
+        } else {
+            // The frame spans multiple descriptors, so it
+            // has to be copied into a contiguous buffer.
+            let mut buffer = [0u8; MTU];
+            let len = self.packet.copy_into(&mut buffer);
+            f(&mut buffer[..len])
+        }
+    }
+}
+
+/// An embassy-net TX token that can be consumed in order to send
+/// an ethernet packet.
+pub struct EmbassyTxToken<'a, 'tx, const N: usize> {
+    tx: &'a mut TxHalf<'tx, N>,
+}
+
+impl<'a, 'tx, const N: usize> TxToken for EmbassyTxToken<'a, 'tx, N> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // The token is only handed out if an entry is
+        // available, so sending can not fail.
+        self.tx
+            .send(len, f)
+            .ok()
+            .expect("TX entry is not available")
+    }
+}
@@ -31,15 +31,23 @@ use {
     stm32::{ETHERNET_DMA, ETHERNET_MAC, ETHERNET_MMC},
 };
 
+#[cfg(feature = "device-selected")]
+pub mod config;
+#[cfg(feature = "device-selected")]
+pub use config::EthernetConfig;
+
 #[cfg(feature = "device-selected")]
 mod dma;
 #[cfg(feature = "device-selected")]
-pub use dma::{eth_interrupt_handler, EthernetDMA};
+pub use dma::{
+    eth_interrupt_handler, DmaStatistics, EthernetDMA, FatalBusError, InterruptReasonSummary,
+    RestartError, RxHalf, TxHalf,
+};
 
 #[cfg(feature = "device-selected")]
 mod ring;
 #[cfg(feature = "device-selected")]
-pub use ring::RingEntry;
+pub use ring::{RingEntry, DEFAULT_BUFFER_SIZE};
 
 mod desc;
 
@@ -51,32 +59,60 @@ pub use mac::{EthernetMAC, EthernetMACWithMii, WrongClock};
 #[cfg(feature = "device-selected")]
 mod rx;
 #[cfg(feature = "device-selected")]
-pub use rx::{RxDescriptor, RxError, RxRingEntry};
+pub use rx::{
+    RxDescriptor, RxError, RxErrorCounts, RxErrors, RxPacket, RxRingEntry, RxStatistics, VlanTag,
+};
 
 #[cfg(feature = "device-selected")]
 mod tx;
 #[cfg(feature = "device-selected")]
-pub use tx::{TxDescriptor, TxError, TxRingEntry};
+pub use tx::{PacketId, TxDescriptor, TxError, TxErrors, TxRingEntry, TxStatistics, TxStatus};
+
+#[cfg(all(feature = "ptp", feature = "device-selected"))]
+pub mod ptp;
+#[cfg(all(feature = "ptp", feature = "device-selected"))]
+pub use ptp::{EthernetPTP, PtpError};
 
 #[cfg(feature = "device-selected")]
 pub mod setup;
 #[cfg(feature = "device-selected")]
-pub use setup::EthPins;
-#[cfg(feature = "device-selected")]
-use setup::{
-    AlternateVeryHighSpeed, RmiiCrsDv, RmiiRefClk, RmiiRxD0, RmiiRxD1, RmiiTxD0, RmiiTxD1, RmiiTxEN,
-};
+pub use setup::{EthPins, InterfacePins, MediaInterface, MiiPins};
 
 #[cfg(all(feature = "smoltcp-phy", feature = "device-selected"))]
 pub use smoltcp;
-#[cfg(all(feature = "smoltcp-phy", feature = "device-selected"))]
+#[cfg(all(
+    feature = "smoltcp-phy-0_9",
+    not(feature = "smoltcp-phy"),
+    feature = "device-selected"
+))]
+pub use smoltcp_0_9 as smoltcp;
+#[cfg(all(
+    any(feature = "smoltcp-phy", feature = "smoltcp-phy-0_9"),
+    feature = "device-selected"
+))]
 mod smoltcp_phy;
-#[cfg(all(feature = "smoltcp-phy", feature = "device-selected"))]
+#[cfg(all(
+    any(feature = "smoltcp-phy", feature = "smoltcp-phy-0_9"),
+    feature = "device-selected"
+))]
 pub use smoltcp_phy::{EthRxToken, EthTxToken};
 
+#[cfg(all(feature = "embassy-net", feature = "device-selected"))]
+pub use embassy_net_driver;
+#[cfg(all(feature = "embassy-net", feature = "device-selected"))]
+mod embassy_net;
+#[cfg(all(feature = "embassy-net", feature = "device-selected"))]
+pub use embassy_net::{request_link_poll, EmbassyDriver, EmbassyRxToken, EmbassyTxToken};
+
 #[cfg(not(feature = "device-selected"))]
 compile_error!("No device was selected! Exactly one stm32fxxx feature must be selected.");
 
+#[cfg(all(feature = "smoltcp-phy", feature = "smoltcp-phy-0_9"))]
+compile_error!("Only one of the `smoltcp-phy` and `smoltcp-phy-0_9` features may be selected.");
+
+#[cfg(all(feature = "ptp", feature = "stm32f107"))]
+compile_error!("The `ptp` feature is not supported on the stm32f107.");
+
 /// From the datasheet: *VLAN Frame maxsize = 1522*
 const MTU: usize = 1522;
 
@@ -84,9 +120,13 @@ const MTU: usize = 1522;
 ///
 /// Initialize and start tx and rx DMA engines.
 /// Sets up the peripheral clocks and GPIO configuration,
-/// and configures the ETH MAC and DMA peripherals.
+/// and configures the ETH MAC and DMA peripherals according
+/// to `config`.
 /// Automatically sets slew rate to VeryHigh.
 ///
+/// The media interface (RMII or MII) is selected based on the type of
+/// `pins`: pass an [`EthPins`] for RMII, or a [`MiiPins`] for MII.
+///
 /// This method does not initialise the external PHY. Interacting with a PHY
 /// can be done by using the struct returned from [`EthernetMAC::mii`].
 ///
@@ -96,38 +136,34 @@ const MTU: usize = 1522;
 /// usually not accessible.
 /// - HCLK must be at least 25 MHz.
 #[cfg(feature = "device-selected")]
-pub fn new<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>(
+pub fn new<'rx, 'tx, PINS, const N: usize>(
     eth_mac: ETHERNET_MAC,
     eth_mmc: ETHERNET_MMC,
     eth_dma: ETHERNET_DMA,
-    rx_buffer: &'rx mut [RxRingEntry],
-    tx_buffer: &'tx mut [TxRingEntry],
+    rx_buffer: &'rx mut [RxRingEntry<N>],
+    tx_buffer: &'tx mut [TxRingEntry<N>],
     clocks: Clocks,
-    pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
-) -> Result<(EthernetDMA<'rx, 'tx>, EthernetMAC), WrongClock>
+    config: EthernetConfig,
+    pins: PINS,
+) -> Result<(EthernetDMA<'rx, 'tx, N>, EthernetMAC), WrongClock>
 where
-    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
-    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
-    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
-    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
-    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
-    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
-    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+    PINS: InterfacePins,
 {
     // Configure all of the pins correctly
     pins.setup_pins();
 
-    // Set up the clocks and reset the MAC periperhal
-    setup::setup();
+    // Set up the clocks, select the media interface
+    // and reset the MAC periperhal
+    setup::setup(PINS::INTERFACE);
 
     // Congfigure and start up the ethernet DMA.
     // Note: this _must_ happen before configuring the MAC.
     // It's not entirely clear why, but no interrupts are
     // generated if the order is reversed.
-    let dma = EthernetDMA::new(eth_dma, &eth_mac, rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(eth_dma, &eth_mac, rx_buffer, tx_buffer, &config);
 
     // Configure the ethernet MAC
-    let mac = EthernetMAC::new(eth_mac, eth_mmc, &dma, clocks)?;
+    let mac = EthernetMAC::new(eth_mac, eth_mmc, &dma, clocks, &config)?;
 
     Ok((dma, mac))
 }
@@ -136,9 +172,13 @@ where
 ///
 /// Initialize and start tx and rx DMA engines.
 /// Sets up the peripheral clocks and GPIO configuration,
-/// and configures the ETH MAC and DMA peripherals.
+/// and configures the ETH MAC and DMA peripherals according
+/// to `config`.
 /// Automatically sets slew rate to VeryHigh.
 ///
+/// The media interface (RMII or MII) is selected based on the type of
+/// `pins`: pass an [`EthPins`] for RMII, or a [`MiiPins`] for MII.
+///
 /// This method does not initialise the external PHY.
 ///
 /// The MII for the external PHY can be accessed through the
@@ -150,42 +190,38 @@ where
 /// usually not accessible.
 /// - HCLK must be at least 25 MHz.
 #[cfg(feature = "device-selected")]
-pub fn new_with_mii<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1, MDIO, MDC>(
+pub fn new_with_mii<'rx, 'tx, PINS, MDIO, MDC, const N: usize>(
     eth_mac: ETHERNET_MAC,
     eth_mmc: ETHERNET_MMC,
     eth_dma: ETHERNET_DMA,
-    rx_buffer: &'rx mut [RxRingEntry],
-    tx_buffer: &'tx mut [TxRingEntry],
+    rx_buffer: &'rx mut [RxRingEntry<N>],
+    tx_buffer: &'tx mut [TxRingEntry<N>],
     clocks: Clocks,
-    pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
+    config: EthernetConfig,
+    pins: PINS,
     mdio: MDIO,
     mdc: MDC,
-) -> Result<(EthernetDMA<'rx, 'tx>, EthernetMACWithMii<MDIO, MDC>), WrongClock>
+) -> Result<(EthernetDMA<'rx, 'tx, N>, EthernetMACWithMii<MDIO, MDC>), WrongClock>
 where
-    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
-    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
-    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
-    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
-    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
-    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
-    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+    PINS: InterfacePins,
     MDIO: mac::MdioPin,
     MDC: mac::MdcPin,
 {
     // Configure all of the pins correctly
     pins.setup_pins();
 
-    // Set up the clocks and reset the MAC periperhal
-    setup::setup();
+    // Set up the clocks, select the media interface
+    // and reset the MAC periperhal
+    setup::setup(PINS::INTERFACE);
 
     // Congfigure and start up the ethernet DMA.
     // Note: this _must_ happen before configuring the MAC.
     // It's not entirely clear why, but no interrupts are
     // generated if the order is reversed.
-    let dma = EthernetDMA::new(eth_dma, &eth_mac, rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(eth_dma, &eth_mac, rx_buffer, tx_buffer, &config);
 
     // Configure the ethernet MAC
-    let mac = EthernetMAC::new(eth_mac, eth_mmc, &dma, clocks)?.with_mii(mdio, mdc);
+    let mac = EthernetMAC::new(eth_mac, eth_mmc, &dma, clocks, &config)?.with_mii(mdio, mdc);
 
     Ok((dma, mac))
 }
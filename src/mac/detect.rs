@@ -0,0 +1,279 @@
+use ieee802_3_miim::{
+    phy::{
+        lan87xxa::{LAN8720A, LAN8742A},
+        Phy, KSZ8081R,
+    },
+    AutoNegotiationAdvertisement, Miim,
+};
+
+use super::{phy_consts::*, FallibleMiim};
+
+/// The amount of addresses on the MDIO bus.
+pub const PHY_ADDRESSES: u8 = 32;
+
+/// The part of a PHY identifier that contains the OUI and
+/// model number, without the revision number.
+const PHY_IDENT_MODEL_MASK: u32 = 0xFFFF_FFF0;
+/// The OUI and model number of the LAN8720A
+const LAN8720A_IDENT: u32 = 0x0007_C0F0;
+/// The OUI and model number of the LAN8742A
+const LAN8742A_IDENT: u32 = 0x0007_C130;
+/// The OUI and model number of the KSZ8081R
+const KSZ8081R_IDENT: u32 = 0x0022_1560;
+
+/// The PHYs that were found on the MDIO bus by [`scan`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyScan {
+    idents: [Option<u32>; PHY_ADDRESSES as usize],
+}
+
+impl PhyScan {
+    /// Get the PHY identifier of the PHY at address `phy_addr`,
+    /// or `None` if no PHY responded at that address.
+    pub fn get(&self, phy_addr: u8) -> Option<u32> {
+        self.idents.get(phy_addr as usize).copied().flatten()
+    }
+
+    /// Iterate over the addresses and PHY identifiers of the
+    /// PHYs that were found, in order of their address.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, u32)> + '_ {
+        self.idents
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, ident)| ident.map(|ident| (addr as u8, ident)))
+    }
+
+    /// Get the address and PHY identifier of the PHY with the lowest address.
+    pub fn first(&self) -> Option<(u8, u32)> {
+        self.iter().next()
+    }
+
+    /// The amount of PHYs that were found.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Check whether no PHYs were found.
+    pub fn is_empty(&self) -> bool {
+        self.first().is_none()
+    }
+}
+
+/// Read the PHY identifier of the PHY at address `phy_addr`.
+///
+/// The MDIO line is pulled up, so an address without a PHY reads as
+/// all ones. Failed MDIO transactions are treated the same way.
+fn read_ident<M: FallibleMiim>(miim: &mut M, phy_addr: u8) -> Option<u32> {
+    let id1 = miim.try_read(phy_addr, PHY_REG_ID1).ok()?;
+    let id2 = miim.try_read(phy_addr, PHY_REG_ID2).ok()?;
+
+    match (id1, id2) {
+        (0xFFFF, 0xFFFF) | (0x0000, 0x0000) => None,
+        (id1, id2) => Some(((id1 as u32) << 16) | id2 as u32),
+    }
+}
+
+/// Probe all 32 addresses of the MDIO bus, and return the PHY
+/// identifier of every PHY that responds.
+///
+/// The PHY identifier consists of the PHY identifier registers 1 and 2,
+/// which contain the OUI, model number and revision number of the PHY.
+pub fn scan<M: FallibleMiim>(miim: &mut M) -> PhyScan {
+    let mut idents = [None; PHY_ADDRESSES as usize];
+
+    for (addr, ident) in idents.iter_mut().enumerate() {
+        *ident = read_ident(miim, addr as u8);
+    }
+
+    PhyScan { idents }
+}
+
+/// A PHY driver that can be detected by [`EthernetPhy`], in
+/// addition to the PHYs that it supports itself.
+pub trait DetectablePhy<M: Miim>: Phy<M> + Sized {
+    /// Create a driver for the PHY at address `phy_addr`, which has the
+    /// PHY identifier `phy_ident`.
+    ///
+    /// Returns `miim` if this driver does not support the PHY.
+    fn from_ident(miim: M, phy_addr: u8, phy_ident: u32) -> Result<Self, M>;
+
+    /// Initialize the PHY
+    fn phy_init(&mut self);
+
+    /// Get a string describing the type of PHY
+    fn ident_string(&self) -> &'static str;
+}
+
+/// The PHY driver of an [`EthernetPhy`] that only detects
+/// the PHYs that are supported by this crate.
+///
+/// This type can not be constructed.
+#[derive(Debug)]
+pub enum NoPhy {}
+
+impl<M: Miim> Phy<M> for NoPhy {
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        match *self {}
+    }
+
+    fn get_miim(&mut self) -> &mut M {
+        match *self {}
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        match *self {}
+    }
+}
+
+impl<M: Miim> DetectablePhy<M> for NoPhy {
+    fn from_ident(miim: M, _phy_addr: u8, _phy_ident: u32) -> Result<Self, M> {
+        Err(miim)
+    }
+
+    fn phy_init(&mut self) {
+        match *self {}
+    }
+
+    fn ident_string(&self) -> &'static str {
+        match *self {}
+    }
+}
+
+/// An ethernet PHY, which is detected by its PHY identifier.
+///
+/// PHYs that are not supported by this crate can be detected
+/// by a user-supplied [`DetectablePhy`] driver `P`.
+pub enum EthernetPhy<M: Miim, P = NoPhy> {
+    /// LAN8720A
+    LAN8720A(LAN8720A<M>),
+    /// LAN8742A
+    LAN8742A(LAN8742A<M>),
+    /// KSZ8081R
+    KSZ8081R(KSZ8081R<M>),
+    /// A PHY that was detected by the user-supplied driver
+    Other(P),
+}
+
+impl<M, P> Phy<M> for EthernetPhy<M, P>
+where
+    M: Miim,
+    P: DetectablePhy<M>,
+{
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        match self {
+            EthernetPhy::LAN8720A(phy) => phy.best_supported_advertisement(),
+            EthernetPhy::LAN8742A(phy) => phy.best_supported_advertisement(),
+            EthernetPhy::KSZ8081R(phy) => phy.best_supported_advertisement(),
+            EthernetPhy::Other(phy) => phy.best_supported_advertisement(),
+        }
+    }
+
+    fn get_miim(&mut self) -> &mut M {
+        match self {
+            EthernetPhy::LAN8720A(phy) => phy.get_miim(),
+            EthernetPhy::LAN8742A(phy) => phy.get_miim(),
+            EthernetPhy::KSZ8081R(phy) => phy.get_miim(),
+            EthernetPhy::Other(phy) => phy.get_miim(),
+        }
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        match self {
+            EthernetPhy::LAN8720A(phy) => phy.get_phy_addr(),
+            EthernetPhy::LAN8742A(phy) => phy.get_phy_addr(),
+            EthernetPhy::KSZ8081R(phy) => phy.get_phy_addr(),
+            EthernetPhy::Other(phy) => phy.get_phy_addr(),
+        }
+    }
+}
+
+impl<M: FallibleMiim> EthernetPhy<M> {
+    /// Attempt to create one of the known PHYs from the given
+    /// MIIM.
+    ///
+    /// Returns `miim` if no PHY responds at address `phy_addr`, or
+    /// if the PHY's identifier does not correspond to a known PHY.
+    pub fn from_miim(miim: M, phy_addr: u8) -> Result<Self, M> {
+        Self::from_miim_with_driver(miim, phy_addr)
+    }
+
+    /// Scan the MDIO bus, and create the first known PHY that is found.
+    ///
+    /// Returns `miim` if no known PHY is found.
+    pub fn detect(miim: M) -> Result<Self, M> {
+        Self::detect_with_driver(miim)
+    }
+}
+
+impl<M, P> EthernetPhy<M, P>
+where
+    M: FallibleMiim,
+    P: DetectablePhy<M>,
+{
+    /// Attempt to create one of the known PHYs, or a PHY that is
+    /// supported by the driver `P`, from the given MIIM.
+    ///
+    /// Returns `miim` if no PHY responds at address `phy_addr`, or
+    /// if the PHY's identifier does not correspond to a known PHY.
+    pub fn from_miim_with_driver(mut miim: M, phy_addr: u8) -> Result<Self, M> {
+        match read_ident(&mut miim, phy_addr) {
+            Some(phy_ident) => Self::from_ident(miim, phy_addr, phy_ident),
+            None => Err(miim),
+        }
+    }
+
+    /// Scan the MDIO bus, and create the first known PHY, or PHY that
+    /// is supported by the driver `P`, that is found.
+    ///
+    /// Returns `miim` if no known PHY is found.
+    pub fn detect_with_driver(mut miim: M) -> Result<Self, M> {
+        let found = scan(&mut miim);
+
+        for (phy_addr, phy_ident) in found.iter() {
+            miim = match Self::from_ident(miim, phy_addr, phy_ident) {
+                Ok(phy) => return Ok(phy),
+                Err(miim) => miim,
+            };
+        }
+
+        Err(miim)
+    }
+
+    fn from_ident(miim: M, phy_addr: u8, phy_ident: u32) -> Result<Self, M> {
+        match phy_ident & PHY_IDENT_MODEL_MASK {
+            LAN8720A_IDENT => Ok(Self::LAN8720A(LAN8720A::new(miim, phy_addr))),
+            LAN8742A_IDENT => Ok(Self::LAN8742A(LAN8742A::new(miim, phy_addr))),
+            KSZ8081R_IDENT => Ok(Self::KSZ8081R(KSZ8081R::new(miim, phy_addr))),
+            _ => P::from_ident(miim, phy_addr, phy_ident).map(Self::Other),
+        }
+    }
+}
+
+impl<M, P> EthernetPhy<M, P>
+where
+    M: Miim,
+    P: DetectablePhy<M>,
+{
+    /// Get a string describing the type of PHY
+    pub fn ident_string(&self) -> &'static str {
+        match self {
+            EthernetPhy::LAN8720A(_) => "LAN8720A",
+            EthernetPhy::LAN8742A(_) => "LAN8742A",
+            EthernetPhy::KSZ8081R(_) => "KSZ8081R",
+            EthernetPhy::Other(phy) => phy.ident_string(),
+        }
+    }
+
+    /// Initialize the PHY
+    pub fn phy_init(&mut self) {
+        match self {
+            EthernetPhy::LAN8720A(phy) => phy.phy_init(),
+            EthernetPhy::LAN8742A(phy) => phy.phy_init(),
+            EthernetPhy::KSZ8081R(phy) => {
+                phy.set_autonegotiation_advertisement(phy.best_supported_advertisement());
+            }
+            EthernetPhy::Other(phy) => phy.phy_init(),
+        }
+    }
+}
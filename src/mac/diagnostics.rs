@@ -0,0 +1,354 @@
+use core::ops::DerefMut;
+
+use ieee802_3_miim::phy::Phy;
+
+use super::{
+    phy_consts::*, DetectablePhy, Duplex, EthernetMAC, EthernetPhy, FallibleMiim, MiimError,
+    SelfTestReport, Speed,
+};
+use crate::EthernetDMA;
+
+/// The amount of times that the PHY is polled for the
+/// result of a cable test.
+const POLL_ATTEMPTS: u32 = 10_000;
+
+/// LinkMD control/status register
+const KSZ8081_REG_LINKMD: u8 = 0x1D;
+/// PHY control 2 register
+const KSZ8081_REG_PHY_CONTROL_2: u8 = 0x1F;
+/// Cable diagnostic test enable
+const KSZ8081_LINKMD_ENABLE: u16 = 1 << 15;
+/// Cable diagnostic test result
+const KSZ8081_LINKMD_RESULT_SHIFT: u16 = 13;
+const KSZ8081_LINKMD_RESULT_MASK: u16 = 0b11 << KSZ8081_LINKMD_RESULT_SHIFT;
+/// Cable fault counter
+const KSZ8081_LINKMD_COUNT_MASK: u16 = 0x1FF;
+/// Disable auto MDI/MDI-X
+const KSZ8081_CONTROL_2_DISABLE_MDIX: u16 = 1 << 13;
+
+/// TDR control/status register
+const LAN8742A_REG_TDR: u8 = 25;
+/// Special control/status indications register
+const LAN8742A_REG_SCSI: u8 = 27;
+/// TDR enable
+const LAN8742A_TDR_ENABLE: u16 = 1 << 15;
+/// TDR analog to digital filter enable
+const LAN8742A_TDR_FILTER: u16 = 1 << 14;
+/// TDR channel cable type
+const LAN8742A_TDR_TYPE_SHIFT: u16 = 9;
+const LAN8742A_TDR_TYPE_MASK: u16 = 0b11 << LAN8742A_TDR_TYPE_SHIFT;
+/// TDR channel status (1 = test complete)
+const LAN8742A_TDR_DONE: u16 = 1 << 8;
+/// TDR channel length
+const LAN8742A_TDR_LENGTH_MASK: u16 = 0xFF;
+/// Manual MDI/MDI-X control
+const LAN8742A_SCSI_AMDIXCTRL: u16 = 1 << 15;
+/// Manual channel select (1 = MDI-X)
+const LAN8742A_SCSI_CH_SELECT: u16 = 1 << 13;
+
+/// The state of the cable that is attached to a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CableStatus {
+    /// No fault was found: the cable is terminated properly.
+    Normal,
+    /// The cable is open (not connected at the other end, or broken).
+    Open,
+    /// The cable is shorted.
+    Short,
+}
+
+/// The result of a [`cable_test`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CableDiagnostics {
+    /// The state of the cable.
+    pub status: CableStatus,
+    /// The approximate distance from the PHY to the fault, in centimeters.
+    ///
+    /// `None` if the cable is [normal](CableStatus::Normal).
+    pub distance_cm: Option<u32>,
+}
+
+/// Errors that can occur during a [`cable_test`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CableTestError {
+    /// The PHY does not support cable diagnostics.
+    Unsupported,
+    /// The PHY did not complete the test in time.
+    Timeout,
+    /// The PHY could not determine the state of the cable.
+    Failed,
+    /// An MDIO transaction with the PHY failed.
+    Mdio(MiimError),
+}
+
+impl From<MiimError> for CableTestError {
+    fn from(error: MiimError) -> Self {
+        CableTestError::Mdio(error)
+    }
+}
+
+/// Enable or disable the loopback mode of `phy`.
+///
+/// In loopback mode, the PHY returns the frames that the MAC transmits
+/// to the MAC, instead of transmitting them on the cable.
+///
+/// Only the loopback bit of the basic control register is changed. Most
+/// PHYs only loop frames back at a fixed speed, so auto-negotiation should
+/// be disabled, and the MAC must be configured for the same speed and
+/// duplex mode as the PHY.
+///
+/// See [`phy_loopback_test`] to test the whole path through the DMA,
+/// the MAC and the PHY.
+///
+/// The basic control register is left untouched if it can not be read.
+pub fn set_phy_loopback<M, P>(phy: &mut P, enable: bool) -> Result<(), MiimError>
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    let addr = phy.get_phy_addr();
+    let miim = phy.get_miim();
+
+    let bcr = miim.try_read(addr, PHY_REG_BCR)? & !PHY_BCR_AN_RESTART;
+    let bcr = if enable {
+        bcr | PHY_BCR_LOOPBACK
+    } else {
+        bcr & !PHY_BCR_LOOPBACK
+    };
+
+    miim.try_write(addr, PHY_REG_BCR, bcr)
+}
+
+/// Whether the loopback mode of `phy` is enabled.
+pub fn phy_loopback<M, P>(phy: &mut P) -> Result<bool, MiimError>
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    let addr = phy.get_phy_addr();
+    let bcr = phy.get_miim().try_read(addr, PHY_REG_BCR)?;
+    Ok(bcr & PHY_BCR_LOOPBACK == PHY_BCR_LOOPBACK)
+}
+
+/// Test the DMA, the MAC and `phy` by sending frames through the
+/// loopback of `phy`, and verifying the frames that are received back.
+///
+/// This sends the same frames as [`EthernetMAC::self_test`], and returns the
+/// same report. `phy` is put in loopback mode at 100 Mbit/s full duplex, and
+/// the MAC is configured to match, for the duration of the test. Afterwards,
+/// the basic control register of `phy` and the configuration of the MAC
+/// are restored. The link goes down during
+/// the test, and auto-negotiation is restarted afterwards if it was enabled.
+///
+/// Some PHYs need some time before the loopback works, which can cause
+/// the first frames to be lost.
+///
+/// Returns an error if an MDIO transaction with `phy` fails, in which
+/// case no test frames are sent.
+pub fn phy_loopback_test<M, P, const N: usize>(
+    phy: &mut P,
+    dma: &mut EthernetDMA<'_, '_, N>,
+) -> Result<SelfTestReport, MiimError>
+where
+    M: FallibleMiim + DerefMut<Target = EthernetMAC>,
+    P: Phy<M>,
+{
+    let addr = phy.get_phy_addr();
+    let bcr = phy.get_miim().try_read(addr, PHY_REG_BCR)?;
+
+    // Loopback at a fixed speed, with auto-negotiation disabled.
+    if let Err(error) = phy.get_miim().try_write(
+        addr,
+        PHY_REG_BCR,
+        PHY_BCR_LOOPBACK | PHY_BCR_SPEED | PHY_BCR_DUPLEX,
+    ) {
+        restore_bcr(phy.get_miim(), addr, bcr).ok();
+        return Err(error);
+    }
+
+    let mac = phy.get_miim().deref_mut();
+    let (speed, duplex) = (mac.speed(), mac.duplex());
+    mac.set_speed(Speed::Mbps100);
+    mac.set_duplex(Duplex::Full);
+
+    let report = mac.loopback_test(dma);
+
+    mac.set_speed(speed);
+    mac.set_duplex(duplex);
+
+    restore_bcr(phy.get_miim(), addr, bcr)?;
+
+    Ok(report)
+}
+
+/// Run the cable diagnostics of `phy`, to find out whether the cable that
+/// is attached to it is open or shorted, and at which distance.
+///
+/// This is supported by the KSZ8081 (LinkMD) and the LAN8742A (TDR), as
+/// detected by [`EthernetPhy`]. Other PHYs return [`CableTestError::Unsupported`].
+///
+/// The link goes down during the test, and auto-negotiation is restarted
+/// afterwards if it was enabled. The link partner should be disconnected or
+/// powered off, as its transmissions interfere with the measurement. The
+/// distance is calculated with the propagation constants of the datasheet
+/// of the PHY, which assume a CAT5 cable.
+///
+/// Failed MDIO transactions are reported as [`CableTestError::Mdio`].
+pub fn cable_test<M, P>(phy: &mut EthernetPhy<M, P>) -> Result<CableDiagnostics, CableTestError>
+where
+    M: FallibleMiim,
+    P: DetectablePhy<M>,
+{
+    let test: fn(&mut M, u8) -> Result<CableDiagnostics, CableTestError> = match phy {
+        EthernetPhy::KSZ8081R(_) => ksz8081_linkmd,
+        EthernetPhy::LAN8742A(_) => lan8742a_tdr,
+        _ => return Err(CableTestError::Unsupported),
+    };
+
+    let addr = phy.get_phy_addr();
+    let miim = phy.get_miim();
+
+    let bcr = miim.try_read(addr, PHY_REG_BCR)?;
+    let result = test(miim, addr);
+    restore_bcr(miim, addr, bcr)?;
+
+    result
+}
+
+/// Run a LinkMD cable diagnostic test on a KSZ8081.
+fn ksz8081_linkmd<M: FallibleMiim>(
+    miim: &mut M,
+    addr: u8,
+) -> Result<CableDiagnostics, CableTestError> {
+    let control_2 = miim.try_read(addr, KSZ8081_REG_PHY_CONTROL_2)?;
+
+    // The test requires 100 Mbit/s without auto-negotiation and auto MDI/MDI-X.
+    let linkmd = miim
+        .try_write(addr, PHY_REG_BCR, PHY_BCR_SPEED | PHY_BCR_DUPLEX)
+        .and_then(|_| {
+            miim.try_write(
+                addr,
+                KSZ8081_REG_PHY_CONTROL_2,
+                control_2 | KSZ8081_CONTROL_2_DISABLE_MDIX,
+            )
+        })
+        .and_then(|_| miim.try_write(addr, KSZ8081_REG_LINKMD, KSZ8081_LINKMD_ENABLE))
+        .and_then(|_| {
+            poll(miim, addr, KSZ8081_REG_LINKMD, |linkmd| {
+                linkmd & KSZ8081_LINKMD_ENABLE == 0
+            })
+        });
+
+    miim.try_write(addr, KSZ8081_REG_PHY_CONTROL_2, control_2)?;
+
+    let linkmd = linkmd?.ok_or(CableTestError::Timeout)?;
+
+    // The distance is 0.38 m for every count above 13.
+    let count = u32::from(linkmd & KSZ8081_LINKMD_COUNT_MASK);
+    let distance_cm = count.saturating_sub(13) * 38;
+
+    match (linkmd & KSZ8081_LINKMD_RESULT_MASK) >> KSZ8081_LINKMD_RESULT_SHIFT {
+        0b00 => Ok(CableDiagnostics {
+            status: CableStatus::Normal,
+            distance_cm: None,
+        }),
+        0b01 => Ok(CableDiagnostics {
+            status: CableStatus::Open,
+            distance_cm: Some(distance_cm),
+        }),
+        0b10 => Ok(CableDiagnostics {
+            status: CableStatus::Short,
+            distance_cm: Some(distance_cm),
+        }),
+        _ => Err(CableTestError::Failed),
+    }
+}
+
+/// Run a time-domain reflectometry (TDR) test on a LAN8742A.
+fn lan8742a_tdr<M: FallibleMiim>(
+    miim: &mut M,
+    addr: u8,
+) -> Result<CableDiagnostics, CableTestError> {
+    let scsi = miim.try_read(addr, LAN8742A_REG_SCSI)?;
+
+    // The test requires 100 Mbit/s without auto-negotiation, and
+    // measures the MDI (transmit) pair.
+    let tdr = miim
+        .try_write(addr, PHY_REG_BCR, PHY_BCR_SPEED | PHY_BCR_DUPLEX)
+        .and_then(|_| {
+            miim.try_write(
+                addr,
+                LAN8742A_REG_SCSI,
+                (scsi | LAN8742A_SCSI_AMDIXCTRL) & !LAN8742A_SCSI_CH_SELECT,
+            )
+        })
+        .and_then(|_| {
+            miim.try_write(
+                addr,
+                LAN8742A_REG_TDR,
+                LAN8742A_TDR_ENABLE | LAN8742A_TDR_FILTER,
+            )
+        })
+        .and_then(|_| {
+            poll(miim, addr, LAN8742A_REG_TDR, |tdr| {
+                tdr & LAN8742A_TDR_DONE == LAN8742A_TDR_DONE
+            })
+        });
+
+    miim.try_write(addr, LAN8742A_REG_TDR, 0)?;
+    miim.try_write(addr, LAN8742A_REG_SCSI, scsi)?;
+
+    let tdr = tdr?.ok_or(CableTestError::Timeout)?;
+    let length = u32::from(tdr & LAN8742A_TDR_LENGTH_MASK);
+
+    // The propagation constants are 0.769 m per count for an
+    // open cable, and 0.745 m per count for a shorted cable.
+    match (tdr & LAN8742A_TDR_TYPE_MASK) >> LAN8742A_TDR_TYPE_SHIFT {
+        0b01 => Ok(CableDiagnostics {
+            status: CableStatus::Short,
+            distance_cm: Some(length * 745 / 10),
+        }),
+        0b10 => Ok(CableDiagnostics {
+            status: CableStatus::Open,
+            distance_cm: Some(length * 769 / 10),
+        }),
+        0b11 => Ok(CableDiagnostics {
+            status: CableStatus::Normal,
+            distance_cm: None,
+        }),
+        _ => Err(CableTestError::Failed),
+    }
+}
+
+/// Read register `reg` until `done` returns true for its value, and
+/// return that value, or `None` if that does not happen in time.
+fn poll<M: FallibleMiim>(
+    miim: &mut M,
+    addr: u8,
+    reg: u8,
+    done: impl Fn(u16) -> bool,
+) -> Result<Option<u16>, MiimError> {
+    for _ in 0..POLL_ATTEMPTS {
+        let value = miim.try_read(addr, reg)?;
+        if done(value) {
+            return Ok(Some(value));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Restore the basic control register to `bcr`, restarting
+/// auto-negotiation if it is enabled.
+fn restore_bcr<M: FallibleMiim>(miim: &mut M, addr: u8, bcr: u16) -> Result<(), MiimError> {
+    let bcr = bcr & !(PHY_BCR_LOOPBACK | PHY_BCR_AN_RESTART);
+
+    if bcr & PHY_BCR_AN_ENABLE == PHY_BCR_AN_ENABLE {
+        miim.try_write(addr, PHY_REG_BCR, bcr | PHY_BCR_AN_RESTART)
+    } else {
+        miim.try_write(addr, PHY_REG_BCR, bcr)
+    }
+}
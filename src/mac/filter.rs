@@ -0,0 +1,494 @@
+use crate::config::FilterMode;
+
+use super::EthernetMAC;
+
+/// 12-bit VLAN tag comparison
+const MACVLANTR_VLANTC: u32 = 1 << 16;
+/// VLAN tag identifier
+const MACVLANTR_VLANTI_MASK: u32 = 0xFFFF;
+
+/// Address enable
+const MACAXHR_AE: u32 = 1 << 31;
+/// Source address
+const MACAXHR_SA: u32 = 1 << 30;
+/// Mask byte control
+const MACAXHR_MBC_SHIFT: u32 = 24;
+const MACAXHR_MBC_MASK: u32 = 0b11_1111 << MACAXHR_MBC_SHIFT;
+
+/// Errors that can occur when joining or leaving a multicast group.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulticastError {
+    /// The address is not a multicast address.
+    NotMulticast,
+    /// No multicast group that hashes to the same bucket as the address
+    /// has been joined.
+    NotJoined,
+    /// Too many multicast groups that hash to the same bucket as the
+    /// address have been joined.
+    TooManyGroups,
+}
+
+/// One of the additional perfect address filter slots of the MAC.
+///
+/// The station address of the MAC (see [`EthernetMAC::set_mac_address`])
+/// always occupies slot 0.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSlot {
+    /// Address slot 1 (`MACA1HR`/`MACA1LR`)
+    Slot1,
+    /// Address slot 2 (`MACA2HR`/`MACA2LR`)
+    Slot2,
+    /// Address slot 3 (`MACA3HR`/`MACA3LR`)
+    Slot3,
+}
+
+impl AddressSlot {
+    const ALL: [AddressSlot; 3] = [AddressSlot::Slot1, AddressSlot::Slot2, AddressSlot::Slot3];
+}
+
+/// A perfect address filter that can be placed in one of the
+/// [`AddressSlot`]s of the MAC.
+///
+/// A destination address filter passes frames that are addressed to
+/// the filter's address, in addition to frames addressed to the station
+/// address.
+///
+/// A source address filter only passes frames that originate from the
+/// filter's address. As soon as one source address filter is enabled,
+/// frames that do not match any source address filter are dropped.
+///
+/// Address filters have no effect if the MAC is in [`FilterMode::ReceiveAll`]
+/// or [`FilterMode::Promiscuous`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressFilter {
+    address: [u8; 6],
+    source: bool,
+    ignored_bytes: u8,
+}
+
+impl AddressFilter {
+    /// Create a filter that matches the destination address of frames.
+    pub const fn destination(address: [u8; 6]) -> Self {
+        Self {
+            address,
+            source: false,
+            ignored_bytes: 0,
+        }
+    }
+
+    /// Create a filter that matches the source address of frames.
+    pub const fn source(address: [u8; 6]) -> Self {
+        Self {
+            address,
+            source: true,
+            ignored_bytes: 0,
+        }
+    }
+
+    /// Ignore some of the bytes of the address when comparing.
+    ///
+    /// If bit `n` of `mask` is set, byte `n` of the address (where byte `0`
+    /// is the first byte that is transmitted) is not compared. Only the
+    /// lower 6 bits of `mask` are used.
+    pub fn ignore_bytes(mut self, mask: u8) -> Self {
+        self.ignored_bytes = mask & 0b11_1111;
+        self
+    }
+
+    /// The address that this filter matches.
+    pub fn address(&self) -> [u8; 6] {
+        self.address
+    }
+
+    /// Whether this filter matches the source address (`true`),
+    /// or the destination address (`false`).
+    pub fn is_source(&self) -> bool {
+        self.source
+    }
+}
+
+/// The VLAN tag filter of the MAC.
+///
+/// Received frames with a VLAN tag that matches the filter are marked as
+/// VLAN frames by the MAC. Use [`EthernetDMA::set_vlan_only`](crate::EthernetDMA::set_vlan_only)
+/// to drop all other frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VlanTagFilter {
+    /// Compare the 12-bit VLAN identifier (VID) of the tag.
+    Vid(u16),
+    /// Compare the full 16-bit tag control information (PCP, DEI and VID)
+    /// of the tag.
+    Tci(u16),
+}
+
+impl VlanTagFilter {
+    fn to_register(self) -> u32 {
+        match self {
+            VlanTagFilter::Vid(vid) => MACVLANTR_VLANTC | (u32::from(vid) & 0xFFF),
+            VlanTagFilter::Tci(tci) => u32::from(tci),
+        }
+    }
+
+    fn from_register(value: u32) -> Option<Self> {
+        let tag = (value & MACVLANTR_VLANTI_MASK) as u16;
+
+        if value & MACVLANTR_VLANTC == MACVLANTR_VLANTC {
+            let vid = tag & 0xFFF;
+            if vid == 0 {
+                None
+            } else {
+                Some(VlanTagFilter::Vid(vid))
+            }
+        } else if tag == 0 {
+            None
+        } else {
+            Some(VlanTagFilter::Tci(tag))
+        }
+    }
+}
+
+/// Split an address into the values of the high and low address registers.
+fn address_to_registers(address: &[u8; 6]) -> (u32, u32) {
+    let high = u32::from(address[4]) | (u32::from(address[5]) << 8);
+    let low = u32::from_le_bytes([address[0], address[1], address[2], address[3]]);
+    (high, low)
+}
+
+/// Combine the values of the high and low address registers into an address.
+fn registers_to_address(high: u32, low: u32) -> [u8; 6] {
+    let low = low.to_le_bytes();
+    [
+        low[0],
+        low[1],
+        low[2],
+        low[3],
+        (high & 0xFF) as u8,
+        ((high >> 8) & 0xFF) as u8,
+    ]
+}
+
+/// Calculate the bucket of the multicast hash table that `address` hashes to.
+///
+/// This is the upper 6 bits of the bit-reversed CRC-32 of the address.
+fn multicast_hash_bucket(address: &[u8; 6]) -> usize {
+    let mut crc: u32 = 0xFFFF_FFFF;
+
+    for byte in address {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    ((!crc).reverse_bits() >> 26) as usize
+}
+
+impl EthernetMAC {
+    /// Set the station address (MAC address) of this MAC.
+    ///
+    /// The station address is used by the destination address filter and
+    /// as the source address of transmitted pause frames.
+    pub fn set_mac_address(&mut self, address: [u8; 6]) {
+        let (high, low) = address_to_registers(&address);
+
+        // The high register must be written first: the address
+        // is only updated after the write to the low register.
+        self.eth_mac.maca0hr.write(|w| unsafe { w.bits(high) });
+        self.eth_mac.maca0lr.write(|w| unsafe { w.bits(low) });
+    }
+
+    /// Get the station address (MAC address) of this MAC.
+    pub fn mac_address(&self) -> [u8; 6] {
+        registers_to_address(
+            self.eth_mac.maca0hr.read().bits(),
+            self.eth_mac.maca0lr.read().bits(),
+        )
+    }
+
+    /// Set the frames that are passed on by the MAC's frame filter.
+    pub fn set_filter_mode(&mut self, filter_mode: FilterMode) {
+        let (receive_all, promiscuous) = match filter_mode {
+            FilterMode::ReceiveAll => (true, true),
+            FilterMode::Promiscuous => (false, true),
+            FilterMode::Filtered => (false, false),
+        };
+
+        self.eth_mac.macffr.modify(|_, w| {
+            // Receive All
+            w.ra()
+                .bit(receive_all)
+                // Promiscuous mode
+                .pm()
+                .bit(promiscuous)
+        });
+    }
+
+    /// Get the frames that are passed on by the MAC's frame filter.
+    pub fn filter_mode(&self) -> FilterMode {
+        let macffr = self.eth_mac.macffr.read();
+        if macffr.ra().bit_is_set() {
+            FilterMode::ReceiveAll
+        } else if macffr.pm().bit_is_set() {
+            FilterMode::Promiscuous
+        } else {
+            FilterMode::Filtered
+        }
+    }
+
+    /// Place `filter` into the perfect address filter `slot`, or
+    /// disable the slot if `filter` is `None`.
+    pub fn set_address_filter(&mut self, slot: AddressSlot, filter: Option<AddressFilter>) {
+        let (high, low) = match filter {
+            Some(filter) => {
+                let (high, low) = address_to_registers(&filter.address);
+                let source = if filter.source { MACAXHR_SA } else { 0 };
+                let mask =
+                    (u32::from(filter.ignored_bytes) << MACAXHR_MBC_SHIFT) & MACAXHR_MBC_MASK;
+                (high | MACAXHR_AE | source | mask, low)
+            }
+            None => (0, 0),
+        };
+
+        // The high register must be written first: the address
+        // is only updated after the write to the low register.
+        match slot {
+            AddressSlot::Slot1 => {
+                self.eth_mac.maca1hr.write(|w| unsafe { w.bits(high) });
+                self.eth_mac.maca1lr.write(|w| unsafe { w.bits(low) });
+            }
+            AddressSlot::Slot2 => {
+                self.eth_mac.maca2hr.write(|w| unsafe { w.bits(high) });
+                self.eth_mac.maca2lr.write(|w| unsafe { w.bits(low) });
+            }
+            AddressSlot::Slot3 => {
+                self.eth_mac.maca3hr.write(|w| unsafe { w.bits(high) });
+                self.eth_mac.maca3lr.write(|w| unsafe { w.bits(low) });
+            }
+        }
+
+        // Only drop frames based on their source address if
+        // at least one source address filter is enabled.
+        let source_filtering = AddressSlot::ALL.iter().any(|slot| {
+            self.address_filter(*slot)
+                .map(|f| f.is_source())
+                .unwrap_or(false)
+        });
+
+        self.eth_mac
+            .macffr
+            .modify(|_, w| w.saf().bit(source_filtering));
+    }
+
+    /// Get the filter that is placed in perfect address filter `slot`,
+    /// or `None` if the slot is disabled.
+    pub fn address_filter(&self, slot: AddressSlot) -> Option<AddressFilter> {
+        let (high, low) = match slot {
+            AddressSlot::Slot1 => (
+                self.eth_mac.maca1hr.read().bits(),
+                self.eth_mac.maca1lr.read().bits(),
+            ),
+            AddressSlot::Slot2 => (
+                self.eth_mac.maca2hr.read().bits(),
+                self.eth_mac.maca2lr.read().bits(),
+            ),
+            AddressSlot::Slot3 => (
+                self.eth_mac.maca3hr.read().bits(),
+                self.eth_mac.maca3lr.read().bits(),
+            ),
+        };
+
+        if high & MACAXHR_AE == 0 {
+            return None;
+        }
+
+        Some(AddressFilter {
+            address: registers_to_address(high, low),
+            source: high & MACAXHR_SA == MACAXHR_SA,
+            ignored_bytes: ((high & MACAXHR_MBC_MASK) >> MACAXHR_MBC_SHIFT) as u8,
+        })
+    }
+}
+
+impl EthernetMAC {
+    /// Pass received frames that are addressed to the multicast group `address`.
+    ///
+    /// The MAC filters multicast frames with a 64-bucket hash table, so frames
+    /// addressed to other multicast groups that hash to the same bucket are
+    /// passed as well. Joining a group more than once is allowed, and requires
+    /// an equal amount of calls to [`EthernetMAC::leave_multicast`].
+    ///
+    /// Multicast filtering has no effect if the MAC is in [`FilterMode::ReceiveAll`]
+    /// or [`FilterMode::Promiscuous`], or if all multicast frames are passed.
+    /// See [`EthernetMAC::set_pass_all_multicast`].
+    pub fn join_multicast(&mut self, address: [u8; 6]) -> Result<(), MulticastError> {
+        if address[0] & 0x01 == 0 {
+            return Err(MulticastError::NotMulticast);
+        }
+
+        let bucket = multicast_hash_bucket(&address);
+        let count = &mut self.multicast_buckets[bucket];
+        *count = count.checked_add(1).ok_or(MulticastError::TooManyGroups)?;
+
+        self.update_multicast_hash_table();
+        Ok(())
+    }
+
+    /// Stop passing received frames that are addressed to the multicast group `address`.
+    ///
+    /// Frames addressed to this group are still passed if another group that hashes
+    /// to the same bucket has been joined.
+    pub fn leave_multicast(&mut self, address: [u8; 6]) -> Result<(), MulticastError> {
+        if address[0] & 0x01 == 0 {
+            return Err(MulticastError::NotMulticast);
+        }
+
+        let bucket = multicast_hash_bucket(&address);
+        let count = &mut self.multicast_buckets[bucket];
+        *count = count.checked_sub(1).ok_or(MulticastError::NotJoined)?;
+
+        self.update_multicast_hash_table();
+        Ok(())
+    }
+
+    /// Pass all received multicast frames, regardless of the
+    /// multicast groups that have been joined.
+    pub fn set_pass_all_multicast(&mut self, pass_all: bool) {
+        self.eth_mac.macffr.modify(|_, w| w.pam().bit(pass_all));
+    }
+
+    /// Whether all received multicast frames are passed.
+    pub fn pass_all_multicast(&self) -> bool {
+        self.eth_mac.macffr.read().pam().bit_is_set()
+    }
+
+    fn update_multicast_hash_table(&mut self) {
+        let mut hash_table: u64 = 0;
+        for (bucket, count) in self.multicast_buckets.iter().enumerate() {
+            if *count > 0 {
+                hash_table |= 1 << bucket;
+            }
+        }
+
+        self.eth_mac
+            .machthr
+            .write(|w| unsafe { w.bits((hash_table >> 32) as u32) });
+        self.eth_mac
+            .machtlr
+            .write(|w| unsafe { w.bits(hash_table as u32) });
+
+        self.eth_mac.macffr.modify(|_, w| {
+            // Hash multicast
+            w.hm()
+                .bit(hash_table != 0)
+                // Hash or perfect filter, so that the perfect
+                // address filters keep working.
+                .hpf()
+                .set_bit()
+        });
+    }
+}
+
+impl EthernetMAC {
+    /// Set the VLAN tag filter of the MAC, or disable it if `filter` is `None`.
+    ///
+    /// If the filter is disabled, all received frames with a VLAN tag
+    /// (EtherType `0x8100`) are marked as VLAN frames. A filter that compares
+    /// to a VID or TCI of zero also disables the filter.
+    pub fn set_vlan_filter(&mut self, filter: Option<VlanTagFilter>) {
+        let value = filter.map(VlanTagFilter::to_register).unwrap_or(0);
+        self.eth_mac.macvlantr.write(|w| unsafe { w.bits(value) });
+    }
+
+    /// Get the VLAN tag filter of the MAC, or `None` if it is disabled.
+    pub fn vlan_filter(&self) -> Option<VlanTagFilter> {
+        VlanTagFilter::from_register(self.eth_mac.macvlantr.read().bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multicast_hash_bucket_known_vectors() {
+        // The bucket is the upper 6 bits of the bit-reversed CRC-32 (as
+        // computed by zlib) of the address.
+        let vectors = [
+            // CRC-32 0x264B3A01
+            ([0x01, 0x00, 0x5E, 0x00, 0x00, 0x01], 32),
+            // CRC-32 0x7B232103
+            ([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB], 48),
+            // CRC-32 0xA2AA2660
+            ([0x33, 0x33, 0x00, 0x00, 0x00, 0x01], 1),
+            // CRC-32 0x603BD48F
+            ([0x01, 0x80, 0xC2, 0x00, 0x00, 0x01], 60),
+            // CRC-32 0x41D9ED00
+            ([0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], 0),
+        ];
+
+        for (address, bucket) in vectors {
+            assert_eq!(multicast_hash_bucket(&address), bucket, "{:02X?}", address);
+        }
+    }
+
+    #[test]
+    fn address_registers_round_trip() {
+        let address = [0x00, 0x80, 0xE1, 0x12, 0x34, 0x56];
+        let (high, low) = address_to_registers(&address);
+
+        assert_eq!(high, 0x5634);
+        assert_eq!(low, 0x12E1_8000);
+        assert_eq!(registers_to_address(high, low), address);
+    }
+
+    #[test]
+    fn vlan_filter_to_register() {
+        assert_eq!(VlanTagFilter::Vid(0x123).to_register(), 0x0001_0123);
+        // Only the lower 12 bits are compared in VID mode
+        assert_eq!(VlanTagFilter::Vid(0xF123).to_register(), 0x0001_0123);
+        assert_eq!(VlanTagFilter::Tci(0xA123).to_register(), 0x0000_A123);
+    }
+
+    #[test]
+    fn vlan_filter_from_register() {
+        assert_eq!(
+            VlanTagFilter::from_register(0x0001_0123),
+            Some(VlanTagFilter::Vid(0x123))
+        );
+        assert_eq!(
+            VlanTagFilter::from_register(0x0001_F123),
+            Some(VlanTagFilter::Vid(0x123))
+        );
+        assert_eq!(
+            VlanTagFilter::from_register(0x0000_A123),
+            Some(VlanTagFilter::Tci(0xA123))
+        );
+
+        // A tag of zero disables the filter
+        assert_eq!(VlanTagFilter::from_register(0x0001_0000), None);
+        assert_eq!(VlanTagFilter::from_register(0x0001_F000), None);
+        assert_eq!(VlanTagFilter::from_register(0), None);
+    }
+
+    #[test]
+    fn vlan_filter_round_trip() {
+        for filter in [
+            VlanTagFilter::Vid(1),
+            VlanTagFilter::Vid(0xFFF),
+            VlanTagFilter::Tci(0xFFFF),
+        ] {
+            assert_eq!(
+                VlanTagFilter::from_register(filter.to_register()),
+                Some(filter)
+            );
+        }
+    }
+}
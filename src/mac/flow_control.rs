@@ -0,0 +1,336 @@
+use ieee802_3_miim::phy::Phy;
+
+use super::{phy_consts::*, phy_link_up, Duplex, EthernetMAC, FallibleMiim};
+
+/// Pause time
+const MACFCR_PT_SHIFT: u32 = 16;
+const MACFCR_PT_MASK: u32 = 0xFFFF << MACFCR_PT_SHIFT;
+/// Zero-quanta pause disable
+const MACFCR_ZQPD: u32 = 1 << 7;
+/// Pause low threshold
+const MACFCR_PLT_SHIFT: u32 = 4;
+const MACFCR_PLT_MASK: u32 = 0b11 << MACFCR_PLT_SHIFT;
+/// Unicast pause frame detect
+const MACFCR_UPFD: u32 = 1 << 3;
+/// Receive flow control enable
+const MACFCR_RFCE: u32 = 1 << 2;
+/// Transmit flow control enable
+const MACFCR_TFCE: u32 = 1 << 1;
+/// Flow control busy / back pressure activate
+const MACFCR_FCB_BPA: u32 = 1 << 0;
+
+/// The directions in which the MAC performs flow control.
+///
+/// In full-duplex mode, flow control uses IEEE 802.3x pause frames. In
+/// half-duplex mode, the MAC can only apply back pressure, which requires
+/// `transmit` flow control. See [`EthernetMAC::set_back_pressure`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControl {
+    /// Transmit pause frames (or apply back pressure in half-duplex mode)
+    /// to ask the link partner to pause its transmissions.
+    pub transmit: bool,
+    /// Pause transmissions when a pause frame is received.
+    pub receive: bool,
+}
+
+impl FlowControl {
+    /// No flow control.
+    pub const NONE: Self = Self {
+        transmit: false,
+        receive: false,
+    };
+
+    /// Symmetric flow control: pause frames are transmitted
+    /// and received.
+    pub const SYMMETRIC: Self = Self {
+        transmit: true,
+        receive: true,
+    };
+}
+
+/// The threshold at which a pause frame is retransmitted, relative
+/// to the [pause time](EthernetMAC::set_pause_time), if the reason
+/// for the pause has not gone away.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseLowThreshold {
+    /// Pause time minus 4 slot times
+    Minus4,
+    /// Pause time minus 28 slot times
+    Minus28,
+    /// Pause time minus 144 slot times
+    Minus144,
+    /// Pause time minus 256 slot times
+    Minus256,
+}
+
+impl PauseLowThreshold {
+    fn bits(self) -> u32 {
+        match self {
+            PauseLowThreshold::Minus4 => 0b00,
+            PauseLowThreshold::Minus28 => 0b01,
+            PauseLowThreshold::Minus144 => 0b10,
+            PauseLowThreshold::Minus256 => 0b11,
+        }
+    }
+}
+
+/// Errors that can occur when transmitting a pause frame
+/// or applying back pressure.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowControlError {
+    /// Transmit flow control is not enabled.
+    TransmitDisabled,
+    /// The MAC is not in the right duplex mode: pause frames can only
+    /// be sent in full-duplex mode, and back pressure can only be
+    /// applied in half-duplex mode.
+    WrongDuplex,
+    /// A pause frame is still being transmitted.
+    Busy,
+}
+
+impl EthernetMAC {
+    /// Set the directions in which the MAC performs flow control.
+    ///
+    /// This should match the flow control that the PHY has negotiated with
+    /// its link partner. See [`negotiated_flow_control`] and
+    /// [`update_link_from_phy`](super::update_link_from_phy), which
+    /// configures it automatically.
+    pub fn set_flow_control(&mut self, flow_control: FlowControl) {
+        let mut bits = 0;
+        if flow_control.transmit {
+            bits |= MACFCR_TFCE;
+        }
+        if flow_control.receive {
+            bits |= MACFCR_RFCE;
+        }
+
+        self.modify_macfcr(MACFCR_TFCE | MACFCR_RFCE, bits);
+    }
+
+    /// Get the directions in which the MAC performs flow control.
+    pub fn flow_control(&self) -> FlowControl {
+        let macfcr = self.eth_mac.macfcr.read().bits();
+        FlowControl {
+            transmit: macfcr & MACFCR_TFCE == MACFCR_TFCE,
+            receive: macfcr & MACFCR_RFCE == MACFCR_RFCE,
+        }
+    }
+
+    /// Set the pause time (in units of 512 bit times) that is sent
+    /// in pause frames.
+    pub fn set_pause_time(&mut self, pause_time: u16) {
+        self.modify_macfcr(MACFCR_PT_MASK, u32::from(pause_time) << MACFCR_PT_SHIFT);
+    }
+
+    /// Get the pause time (in units of 512 bit times) that is sent
+    /// in pause frames.
+    pub fn pause_time(&self) -> u16 {
+        ((self.eth_mac.macfcr.read().bits() & MACFCR_PT_MASK) >> MACFCR_PT_SHIFT) as u16
+    }
+
+    /// Set the threshold at which a pause frame is retransmitted.
+    pub fn set_pause_low_threshold(&mut self, threshold: PauseLowThreshold) {
+        self.modify_macfcr(MACFCR_PLT_MASK, threshold.bits() << MACFCR_PLT_SHIFT);
+    }
+
+    /// Enable or disable the automatic transmission of a pause frame with a
+    /// pause time of zero (zero-quanta pause frame) once the reason for
+    /// a pause has gone away.
+    pub fn set_zero_quanta_pause(&mut self, enable: bool) {
+        let bits = if enable { 0 } else { MACFCR_ZQPD };
+        self.modify_macfcr(MACFCR_ZQPD, bits);
+    }
+
+    /// Enable or disable the detection of pause frames that are addressed
+    /// to the station address of the MAC, in addition to pause frames that
+    /// are addressed to the reserved multicast address `01-80-C2-00-00-01`.
+    pub fn set_unicast_pause_frame_detect(&mut self, enable: bool) {
+        let bits = if enable { MACFCR_UPFD } else { 0 };
+        self.modify_macfcr(MACFCR_UPFD, bits);
+    }
+
+    /// Transmit a pause frame, which asks the link partner to pause its
+    /// transmissions for the [pause time](EthernetMAC::set_pause_time).
+    ///
+    /// A pause frame can only be sent in full-duplex mode, with
+    /// transmit flow control enabled.
+    pub fn send_pause_frame(&mut self) -> Result<(), FlowControlError> {
+        if self.duplex() != Duplex::Full {
+            return Err(FlowControlError::WrongDuplex);
+        }
+
+        let macfcr = self.eth_mac.macfcr.read().bits();
+
+        if macfcr & MACFCR_TFCE == 0 {
+            return Err(FlowControlError::TransmitDisabled);
+        }
+
+        if macfcr & MACFCR_FCB_BPA == MACFCR_FCB_BPA {
+            return Err(FlowControlError::Busy);
+        }
+
+        self.eth_mac
+            .macfcr
+            .write(|w| unsafe { w.bits(macfcr | MACFCR_FCB_BPA) });
+
+        Ok(())
+    }
+
+    /// Whether a pause frame is currently being transmitted.
+    pub fn is_sending_pause_frame(&self) -> bool {
+        self.duplex() == Duplex::Full
+            && self.eth_mac.macfcr.read().bits() & MACFCR_FCB_BPA == MACFCR_FCB_BPA
+    }
+
+    /// Apply or release back pressure, which causes collisions with the
+    /// frames that the link partner transmits.
+    ///
+    /// Back pressure can only be applied in half-duplex mode, with
+    /// transmit flow control enabled.
+    pub fn set_back_pressure(&mut self, enable: bool) -> Result<(), FlowControlError> {
+        if self.duplex() != Duplex::Half {
+            return Err(FlowControlError::WrongDuplex);
+        }
+
+        if enable && self.eth_mac.macfcr.read().bits() & MACFCR_TFCE == 0 {
+            return Err(FlowControlError::TransmitDisabled);
+        }
+
+        let bits = if enable { MACFCR_FCB_BPA } else { 0 };
+        self.modify_macfcr(MACFCR_FCB_BPA, bits);
+
+        Ok(())
+    }
+
+    /// Replace the bits in `mask` of `MACFCR` with `bits`.
+    ///
+    /// In full-duplex mode, the flow control busy bit is never set by this
+    /// function, as writing it would transmit a pause frame. In half-duplex
+    /// mode, the back pressure activate bit keeps its current value.
+    fn modify_macfcr(&mut self, mask: u32, bits: u32) {
+        let full_duplex = self.duplex() == Duplex::Full;
+
+        self.eth_mac.macfcr.modify(|r, w| {
+            let mut value = (r.bits() & !mask) | (bits & mask);
+            if full_duplex && mask & MACFCR_FCB_BPA == 0 {
+                value &= !MACFCR_FCB_BPA;
+            }
+            unsafe { w.bits(value) }
+        });
+    }
+}
+
+/// Resolve the flow control that `phy` has negotiated with its link partner,
+/// from the pause abilities that both have advertised.
+///
+/// Returns `None` if the link is down, if auto-negotiation is disabled
+/// or has not completed (yet), or if the PHY does not respond.
+pub fn negotiated_flow_control<M, P>(phy: &mut P) -> Option<FlowControl>
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    if !phy_link_up(phy) {
+        return None;
+    }
+
+    let addr = phy.get_phy_addr();
+    let miim = phy.get_miim();
+
+    if miim.try_read(addr, PHY_REG_BCR).ok()? & PHY_BCR_AN_ENABLE == 0
+        || miim.try_read(addr, PHY_REG_BSR).ok()? & PHY_BSR_AN_COMPLETE == 0
+    {
+        return None;
+    }
+
+    let local = miim.try_read(addr, PHY_REG_ANAR).ok()?;
+    let partner = miim.try_read(addr, PHY_REG_ANLPAR).ok()?;
+
+    Some(resolve_pause(local, partner))
+}
+
+/// Resolve the flow control from the `local` and `partner` auto-negotiation
+/// advertisements, as specified by IEEE 802.3 Annex 28B.3.
+fn resolve_pause(local: u16, partner: u16) -> FlowControl {
+    let pause = |reg: u16| reg & PHY_AN_PAUSE == PHY_AN_PAUSE;
+    let asym = |reg: u16| reg & PHY_AN_ASYM_PAUSE == PHY_AN_ASYM_PAUSE;
+
+    if pause(local) && pause(partner) {
+        FlowControl::SYMMETRIC
+    } else if !pause(local) && asym(local) && pause(partner) && asym(partner) {
+        FlowControl {
+            transmit: true,
+            receive: false,
+        }
+    } else if pause(local) && asym(local) && !pause(partner) && asym(partner) {
+        FlowControl {
+            transmit: false,
+            receive: true,
+        }
+    } else {
+        FlowControl::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: u16 = 0;
+    const PAUSE: u16 = PHY_AN_PAUSE;
+    const ASYM: u16 = PHY_AN_ASYM_PAUSE;
+    const BOTH: u16 = PHY_AN_PAUSE | PHY_AN_ASYM_PAUSE;
+
+    const TX_ONLY: FlowControl = FlowControl {
+        transmit: true,
+        receive: false,
+    };
+    const RX_ONLY: FlowControl = FlowControl {
+        transmit: false,
+        receive: true,
+    };
+
+    #[test]
+    fn pause_resolution_table() {
+        // Table 28B-3 of IEEE 802.3, as (local, partner, resolution)
+        let table = [
+            (NONE, NONE, FlowControl::NONE),
+            (NONE, PAUSE, FlowControl::NONE),
+            (NONE, ASYM, FlowControl::NONE),
+            (NONE, BOTH, FlowControl::NONE),
+            (PAUSE, NONE, FlowControl::NONE),
+            (PAUSE, PAUSE, FlowControl::SYMMETRIC),
+            (PAUSE, ASYM, FlowControl::NONE),
+            (PAUSE, BOTH, FlowControl::SYMMETRIC),
+            (ASYM, NONE, FlowControl::NONE),
+            (ASYM, PAUSE, FlowControl::NONE),
+            (ASYM, ASYM, FlowControl::NONE),
+            (ASYM, BOTH, TX_ONLY),
+            (BOTH, NONE, FlowControl::NONE),
+            (BOTH, PAUSE, FlowControl::SYMMETRIC),
+            (BOTH, ASYM, RX_ONLY),
+            (BOTH, BOTH, FlowControl::SYMMETRIC),
+        ];
+
+        for (local, partner, expected) in table {
+            assert_eq!(
+                resolve_pause(local, partner),
+                expected,
+                "local {:#06x}, partner {:#06x}",
+                local,
+                partner
+            );
+        }
+    }
+
+    #[test]
+    fn pause_resolution_ignores_other_abilities() {
+        let abilities = PHY_AN_100BASE_TX_FD | PHY_AN_10BASE_T_HD | 0x0001;
+
+        assert_eq!(resolve_pause(BOTH | abilities, ASYM | abilities), RX_ONLY);
+        assert_eq!(resolve_pause(abilities, abilities), FlowControl::NONE);
+    }
+}
@@ -21,13 +21,66 @@ pub unsafe trait MdioPin {}
 /// may implement this trait
 pub unsafe trait MdcPin {}
 
-#[inline(always)]
-fn miim_wait_ready(iar: &MACMIIAR) {
-    while iar.read().mb().bit_is_set() {}
+/// The amount of times that the MII busy bit is polled before an MDIO
+/// transaction is considered to have timed out. A transaction takes
+/// 64 MDC cycles, which is at most 6528 HCLK cycles.
+const MIIM_WAIT_ATTEMPTS: u32 = 100_000;
+
+/// Errors that can occur during an MDIO transaction.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiimError {
+    /// An MDIO transaction is still in progress.
+    WouldBlock,
+    /// The MDIO transaction did not complete in time.
+    Timeout,
+    /// No read (when polling a read) or write (when polling a write)
+    /// was started, or its completion was already returned.
+    NotStarted,
+}
+
+/// MDIO access that reports whether a transaction failed, which
+/// the [`Miim`] trait can not.
+///
+/// Functions that determine the state of the link, such as
+/// [`negotiated_link`](super::negotiated_link), and [`scan`](super::scan)
+/// require this trait, so that a dead MDIO bus is not mistaken for a PHY.
+pub trait FallibleMiim: Miim {
+    /// Read MII register `reg` from the PHY at address `phy`,
+    /// or return an error if the transaction failed.
+    fn try_read(&mut self, phy: u8, reg: u8) -> Result<u16, MiimError>;
+
+    /// Write the value `data` to MII register `reg` to the PHY at
+    /// address `phy`, or return an error if the transaction failed.
+    fn try_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError>;
+}
+
+/// The kind of MDIO transaction that was started without waiting for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum MiimOperation {
+    Read,
+    Write,
 }
 
 #[inline(always)]
-fn miim_write(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8, data: u16) {
+fn miim_is_busy(iar: &MACMIIAR) -> bool {
+    iar.read().mb().bit_is_set()
+}
+
+fn miim_wait_ready(iar: &MACMIIAR) -> Result<(), MiimError> {
+    if (0..MIIM_WAIT_ATTEMPTS).any(|_| !miim_is_busy(iar)) {
+        Ok(())
+    } else {
+        Err(MiimError::Timeout)
+    }
+}
+
+fn miim_start_write(mac: &mut EthernetMAC, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+    let eth_mac = &mut mac.eth_mac;
+    if miim_is_busy(&eth_mac.macmiiar) {
+        return Err(MiimError::WouldBlock);
+    }
+
     eth_mac.macmiidr.write(|w| w.md().bits(data));
 
     eth_mac.macmiiar.modify(|_, w| {
@@ -41,11 +94,17 @@ fn miim_write(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8, data: u16) {
             .mb()
             .set_bit()
     });
-    miim_wait_ready(&eth_mac.macmiiar);
+
+    mac.miim_pending = Some(MiimOperation::Write);
+    Ok(())
 }
 
-#[inline(always)]
-fn miim_read(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8) -> u16 {
+fn miim_start_read(mac: &mut EthernetMAC, phy: u8, reg: u8) -> Result<(), MiimError> {
+    let eth_mac = &mut mac.eth_mac;
+    if miim_is_busy(&eth_mac.macmiiar) {
+        return Err(MiimError::WouldBlock);
+    }
+
     eth_mac.macmiiar.modify(|_, w| {
         w.pa()
             .bits(phy)
@@ -57,10 +116,60 @@ fn miim_read(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8) -> u16 {
             .mb()
             .set_bit()
     });
-    miim_wait_ready(&eth_mac.macmiiar);
+
+    mac.miim_pending = Some(MiimOperation::Read);
+    Ok(())
+}
+
+/// Complete the pending `operation`, if it has finished.
+fn miim_poll(mac: &mut EthernetMAC, operation: MiimOperation) -> Result<(), MiimError> {
+    if mac.miim_pending != Some(operation) {
+        return Err(MiimError::NotStarted);
+    }
+
+    if miim_is_busy(&mac.eth_mac.macmiiar) {
+        return Err(MiimError::WouldBlock);
+    }
+
+    mac.miim_pending = None;
+    Ok(())
+}
+
+fn miim_poll_write(mac: &mut EthernetMAC) -> Result<(), MiimError> {
+    miim_poll(mac, MiimOperation::Write)
+}
+
+fn miim_poll_read(mac: &mut EthernetMAC) -> Result<u16, MiimError> {
+    miim_poll(mac, MiimOperation::Read)?;
 
     // Return value:
-    eth_mac.macmiidr.read().md().bits()
+    Ok(mac.eth_mac.macmiidr.read().md().bits())
+}
+
+fn miim_try_write(mac: &mut EthernetMAC, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+    // A transaction that was started earlier may still be in progress.
+    miim_wait_ready(&mac.eth_mac.macmiiar)?;
+    miim_start_write(mac, phy, reg, data)?;
+    miim_wait_ready(&mac.eth_mac.macmiiar)?;
+    miim_poll_write(mac)
+}
+
+fn miim_try_read(mac: &mut EthernetMAC, phy: u8, reg: u8) -> Result<u16, MiimError> {
+    miim_wait_ready(&mac.eth_mac.macmiiar)?;
+    miim_start_read(mac, phy, reg)?;
+    miim_wait_ready(&mac.eth_mac.macmiiar)?;
+    miim_poll_read(mac)
+}
+
+#[inline(always)]
+fn miim_write(mac: &mut EthernetMAC, phy: u8, reg: u8, data: u16) {
+    miim_try_write(mac, phy, reg, data).ok();
+}
+
+#[inline(always)]
+fn miim_read(mac: &mut EthernetMAC, phy: u8, reg: u8) -> u16 {
+    // A missing PHY does not drive MDIO, which reads as all ones.
+    miim_try_read(mac, phy, reg).unwrap_or(0xFFFF)
 }
 
 /// Serial Management Interface
@@ -78,13 +187,76 @@ where
     Mdc: MdcPin,
 {
     /// Read MII register `reg` from the PHY at address `phy`
+    ///
+    /// Returns `0xFFFF` if the transaction times out. Use
+    /// [`Stm32Mii::try_read`] to detect timeouts.
     pub fn read(&mut self, phy: u8, reg: u8) -> u16 {
-        miim_read(&mut self.mac.eth_mac, phy, reg)
+        miim_read(self.mac, phy, reg)
     }
 
     /// Write the value `data` to MII register `reg` to the PHY at address `phy`
+    ///
+    /// Timeouts are ignored. Use [`Stm32Mii::try_write`] to detect them.
     pub fn write(&mut self, phy: u8, reg: u8, data: u16) {
-        miim_write(&mut self.mac.eth_mac, phy, reg, data)
+        miim_write(self.mac, phy, reg, data)
+    }
+
+    /// Read MII register `reg` from the PHY at address `phy`, or return
+    /// [`MiimError::Timeout`] if the transaction does not complete in time.
+    pub fn try_read(&mut self, phy: u8, reg: u8) -> Result<u16, MiimError> {
+        miim_try_read(self.mac, phy, reg)
+    }
+
+    /// Write the value `data` to MII register `reg` to the PHY at address `phy`, or
+    /// return [`MiimError::Timeout`] if the transaction does not complete in time.
+    pub fn try_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        miim_try_write(self.mac, phy, reg, data)
+    }
+
+    /// Start reading MII register `reg` from the PHY at address `phy`, without
+    /// waiting for the transaction to complete. Use [`Stm32Mii::poll_read`] to
+    /// obtain the value.
+    ///
+    /// Returns [`MiimError::WouldBlock`] if another transaction is in progress.
+    /// The result of a completed transaction that has not been polled yet
+    /// is discarded.
+    pub fn start_read(&mut self, phy: u8, reg: u8) -> Result<(), MiimError> {
+        miim_start_read(self.mac, phy, reg)
+    }
+
+    /// Start writing the value `data` to MII register `reg` to the PHY at address
+    /// `phy`, without waiting for the transaction to complete. Use
+    /// [`Stm32Mii::poll_write`] to wait for it.
+    ///
+    /// Returns [`MiimError::WouldBlock`] if another transaction is in progress.
+    /// The result of a completed transaction that has not been polled yet
+    /// is discarded.
+    pub fn start_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        miim_start_write(self.mac, phy, reg, data)
+    }
+
+    /// Get the value of the read that was started with [`Stm32Mii::start_read`],
+    /// or [`MiimError::WouldBlock`] if it is still in progress.
+    ///
+    /// Returns [`MiimError::NotStarted`] if no read is pending.
+    pub fn poll_read(&mut self) -> Result<u16, MiimError> {
+        miim_poll_read(self.mac)
+    }
+
+    /// Check whether the write that was started with [`Stm32Mii::start_write`]
+    /// has completed, or return [`MiimError::WouldBlock`] if it is still in progress.
+    ///
+    /// Returns [`MiimError::NotStarted`] if no write is pending.
+    pub fn poll_write(&mut self) -> Result<(), MiimError> {
+        miim_poll_write(self.mac)
+    }
+
+    /// Probe all 32 addresses of the MDIO bus, and return the PHY
+    /// identifier of every PHY that responds.
+    ///
+    /// See [`scan`](super::scan).
+    pub fn scan(&mut self) -> super::PhyScan {
+        super::scan(self)
     }
 }
 
@@ -102,6 +274,20 @@ where
     }
 }
 
+impl<'eth, 'pins, Mdio, Mdc> FallibleMiim for Stm32Mii<'eth, 'pins, Mdio, Mdc>
+where
+    Mdio: MdioPin,
+    Mdc: MdcPin,
+{
+    fn try_read(&mut self, phy: u8, reg: u8) -> Result<u16, MiimError> {
+        self.try_read(phy, reg)
+    }
+
+    fn try_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        self.try_write(phy, reg, data)
+    }
+}
+
 impl<'eth, 'pins, Mdio, Mdc> Stm32Mii<'eth, 'pins, Mdio, Mdc>
 where
     Mdio: MdioPin,
@@ -0,0 +1,173 @@
+use crate::stm32::ETHERNET_MMC;
+
+use super::EthernetMAC;
+
+/// Counter reset
+const MMCCR_CR: u32 = 1 << 0;
+/// Counter stop rollover
+const MMCCR_CSR: u32 = 1 << 1;
+/// Reset on read
+const MMCCR_ROR: u32 = 1 << 2;
+/// MMC counter freeze
+const MMCCR_MCF: u32 = 1 << 3;
+
+/// The amount of times that `MMCCR.CR` is polled before
+/// the counters are assumed to have been reset.
+const RESET_WAIT_ATTEMPTS: u32 = 100_000;
+
+/// Statistics that are gathered by the MAC management
+/// counters (MMC) of the MAC.
+///
+/// The hardware counters are 32 bits wide. They are accumulated into
+/// 64-bit totals every time the statistics are read, so the totals do not
+/// roll over as long as the statistics are read at least once for every
+/// 2<sup>32</sup> counted frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Frames that were transmitted successfully after
+    /// a single collision in half-duplex mode.
+    pub tx_single_collision_good_frames: u64,
+    /// Frames that were transmitted successfully after
+    /// more than one collision in half-duplex mode.
+    pub tx_multiple_collision_good_frames: u64,
+    /// Frames that were transmitted successfully.
+    pub tx_good_frames: u64,
+    /// Frames that were received with a CRC error.
+    pub rx_crc_errors: u64,
+    /// Frames that were received with an alignment
+    /// (dribble) error.
+    pub rx_alignment_errors: u64,
+    /// Unicast frames that were received successfully.
+    pub rx_good_unicast_frames: u64,
+}
+
+/// The MMC of the MAC, and the state that is required to
+/// accumulate its counters.
+pub(crate) struct Mmc {
+    eth_mmc: ETHERNET_MMC,
+    totals: Statistics,
+    /// The values of the hardware counters when they were last read,
+    /// in the order of the fields of [`Statistics`].
+    last: [u32; 6],
+}
+
+impl Mmc {
+    pub(crate) fn new(eth_mmc: ETHERNET_MMC) -> Self {
+        // Disable all MMC RX interrupts
+        eth_mmc
+            .mmcrimr
+            .write(|w| w.rgufm().set_bit().rfaem().set_bit().rfcem().set_bit());
+
+        // Disable all MMC TX interrupts
+        eth_mmc
+            .mmctimr
+            .write(|w| w.tgfm().set_bit().tgfmscm().set_bit().tgfscm().set_bit());
+
+        // Fix incorrect TGFM bit position until https://github.com/stm32-rs/stm32-rs/pull/689
+        // is released and used by HALs.
+        eth_mmc
+            .mmctimr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 21)) });
+
+        // Let the counters roll over, so that they can be accumulated.
+        eth_mmc
+            .mmccr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !MMCCR_CSR) });
+
+        let mut mmc = Self {
+            eth_mmc,
+            totals: Statistics::default(),
+            last: [0; 6],
+        };
+
+        mmc.reset();
+        mmc
+    }
+
+    fn modify_mmccr(&mut self, bit: u32, set: bool) {
+        self.eth_mmc.mmccr.modify(|r, w| unsafe {
+            if set {
+                w.bits(r.bits() | bit)
+            } else {
+                w.bits(r.bits() & !bit)
+            }
+        });
+    }
+
+    fn reset(&mut self) {
+        self.modify_mmccr(MMCCR_CR, true);
+        // The counter reset bit clears itself. The wait is bounded, so that
+        // creating the MAC can not hang if the MAC is not clocked.
+        for _ in 0..RESET_WAIT_ATTEMPTS {
+            if self.eth_mmc.mmccr.read().bits() & MMCCR_CR == 0 {
+                break;
+            }
+        }
+
+        self.totals = Statistics::default();
+        self.last = [0; 6];
+    }
+
+    fn update(&mut self) -> Statistics {
+        let reset_on_read = self.eth_mmc.mmccr.read().bits() & MMCCR_ROR == MMCCR_ROR;
+
+        let mmc = &self.eth_mmc;
+        let counters = [
+            mmc.mmctgfsccr.read().bits(),
+            mmc.mmctgfmsccr.read().bits(),
+            mmc.mmctgfcr.read().bits(),
+            mmc.mmcrfcecr.read().bits(),
+            mmc.mmcrfaecr.read().bits(),
+            mmc.mmcrgufcr.read().bits(),
+        ];
+
+        let mut deltas = [0u64; 6];
+        for ((delta, counter), last) in deltas.iter_mut().zip(counters).zip(&mut self.last) {
+            // Counters that are reset on read count from zero every time.
+            *delta = u64::from(counter.wrapping_sub(*last));
+            *last = if reset_on_read { 0 } else { counter };
+        }
+
+        let totals = &mut self.totals;
+        totals.tx_single_collision_good_frames += deltas[0];
+        totals.tx_multiple_collision_good_frames += deltas[1];
+        totals.tx_good_frames += deltas[2];
+        totals.rx_crc_errors += deltas[3];
+        totals.rx_alignment_errors += deltas[4];
+        totals.rx_good_unicast_frames += deltas[5];
+
+        *totals
+    }
+}
+
+impl EthernetMAC {
+    /// Read the statistics that the MMC has gathered since it
+    /// was last reset.
+    pub fn statistics(&mut self) -> Statistics {
+        self.mmc.update()
+    }
+
+    /// Reset all statistics to zero.
+    pub fn reset_statistics(&mut self) {
+        self.mmc.reset();
+    }
+
+    /// Freeze or unfreeze the MMC counters.
+    ///
+    /// While the counters are frozen, frames are not counted.
+    pub fn freeze_statistics(&mut self, freeze: bool) {
+        self.mmc.modify_mmccr(MMCCR_MCF, freeze);
+    }
+
+    /// Enable or disable resetting the hardware counters whenever they are read.
+    ///
+    /// This does not affect the values that are returned by
+    /// [`EthernetMAC::statistics`], which are accumulated either way.
+    pub fn set_statistics_reset_on_read(&mut self, enable: bool) {
+        // Accumulate the counts up to now with the current mode, so that
+        // the next read starts from a known value.
+        self.mmc.update();
+        self.mmc.modify_mmccr(MMCCR_ROR, enable);
+    }
+}
@@ -1,16 +1,35 @@
 //! Ethernet MAC driver implementation
 
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
 
 use crate::{
     hal::rcc::Clocks,
     stm32::{ETHERNET_MAC, ETHERNET_MMC},
-    EthernetDMA,
+    EthernetConfig, EthernetDMA,
 };
 
 mod miim;
 pub use miim::*;
 
+mod filter;
+pub use filter::*;
+
+mod flow_control;
+pub use flow_control::*;
+
+mod mmc;
+use mmc::Mmc;
+pub use mmc::Statistics;
+
+mod self_test;
+pub use self_test::SelfTestReport;
+
+mod diagnostics;
+pub use diagnostics::*;
+
+mod detect;
+pub use detect::*;
+
 mod consts {
     /* For HCLK 60-100 MHz */
     pub const ETH_MACMIIAR_CR_HCLK_DIV_42: u8 = 0;
@@ -25,15 +44,81 @@ mod consts {
 }
 use self::consts::*;
 
+/// Registers and bits of the IEEE 802.3 basic register set
+/// that are used to determine the negotiated link, and to
+/// run diagnostics.
+mod phy_consts {
+    pub const PHY_REG_BCR: u8 = 0x00;
+    pub const PHY_REG_BSR: u8 = 0x01;
+    pub const PHY_REG_ID1: u8 = 0x02;
+    pub const PHY_REG_ID2: u8 = 0x03;
+    pub const PHY_REG_ANAR: u8 = 0x04;
+    pub const PHY_REG_ANLPAR: u8 = 0x05;
+
+    /// Loopback
+    pub const PHY_BCR_LOOPBACK: u16 = 1 << 14;
+    /// Speed selection (1 = 100 Mbit/s)
+    pub const PHY_BCR_SPEED: u16 = 1 << 13;
+    /// Auto-negotiation enable
+    pub const PHY_BCR_AN_ENABLE: u16 = 1 << 12;
+    /// Restart auto-negotiation
+    pub const PHY_BCR_AN_RESTART: u16 = 1 << 9;
+    /// Duplex mode (1 = full duplex)
+    pub const PHY_BCR_DUPLEX: u16 = 1 << 8;
+
+    /// Auto-negotiation complete
+    pub const PHY_BSR_AN_COMPLETE: u16 = 1 << 5;
+    /// Link status
+    pub const PHY_BSR_LINK_STATUS: u16 = 1 << 2;
+
+    /// Asymmetric pause
+    pub const PHY_AN_ASYM_PAUSE: u16 = 1 << 11;
+    /// Pause
+    pub const PHY_AN_PAUSE: u16 = 1 << 10;
+    pub const PHY_AN_100BASE_T4: u16 = 1 << 9;
+    pub const PHY_AN_100BASE_TX_FD: u16 = 1 << 8;
+    pub const PHY_AN_100BASE_TX_HD: u16 = 1 << 7;
+    pub const PHY_AN_10BASE_T_FD: u16 = 1 << 6;
+    pub const PHY_AN_10BASE_T_HD: u16 = 1 << 5;
+}
+use self::phy_consts::*;
+
 /// HCLK must be at least 25MHz to use the ethernet peripheral.
 /// This (empty) struct is returned to indicate that it is not set
 /// correctly
 #[derive(Debug)]
 pub struct WrongClock;
 
+/// The speed of the link between the MAC and the PHY
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    /// 10 Mbit/s
+    Mbps10,
+    /// 100 Mbit/s
+    Mbps100,
+}
+
+/// The duplex mode of the link between the MAC and the PHY
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    /// Half duplex
+    Half,
+    /// Full duplex
+    Full,
+}
+
 /// Ethernet media access control (MAC).
 pub struct EthernetMAC {
     pub(crate) eth_mac: ETHERNET_MAC,
+    mmc: Mmc,
+    /// The amount of joined multicast groups per
+    /// bucket of the multicast hash table.
+    multicast_buckets: [u8; 64],
+    /// The MDIO transaction that was started with
+    /// [`Stm32Mii::start_read`] or [`Stm32Mii::start_write`].
+    miim_pending: Option<MiimOperation>,
 }
 
 impl EthernetMAC {
@@ -46,14 +131,15 @@ impl EthernetMAC {
     ///
     /// Additionally, an `impl` of the [`ieee802_3_miim::Miim`] trait is available
     /// for PHY communication.
-    pub(crate) fn new(
+    pub(crate) fn new<const N: usize>(
         eth_mac: ETHERNET_MAC,
         eth_mmc: ETHERNET_MMC,
         // Take a reference to EthernetDMA to ensure
         // that `EthernetDMA` has been called before
         // this function.
-        #[allow(unused)] eth_dma: &EthernetDMA,
+        #[allow(unused)] eth_dma: &EthernetDMA<'_, '_, N>,
         clocks: Clocks,
+        config: &EthernetConfig,
     ) -> Result<Self, WrongClock> {
         let clock_frequency = clocks.hclk().to_Hz();
 
@@ -75,23 +161,23 @@ impl EthernetMAC {
         eth_mac.maccr.modify(|_, w| {
             // CRC stripping for Type frames. STM32F1xx do not have this bit.
             #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
-            let w = w.cstf().set_bit();
+            let w = w.cstf().bit(config.crc_stripping);
 
             // Fast Ethernet speed
             w.fes()
-                .set_bit()
+                .bit(config.speed == Speed::Mbps100)
                 // Duplex mode
                 .dm()
-                .set_bit()
+                .bit(config.duplex == Duplex::Full)
                 // IPv4 checksum offload
                 .ipco()
-                .set_bit()
+                .bit(config.rx_checksum_offload)
                 // Automatic pad/CRC stripping
                 .apcs()
-                .set_bit()
+                .bit(config.crc_stripping)
                 // Retry disable in half-duplex mode
                 .rd()
-                .set_bit()
+                .bit(!config.retry)
                 // Receiver enable
                 .re()
                 .set_bit()
@@ -100,39 +186,66 @@ impl EthernetMAC {
                 .set_bit()
         });
 
+        let mut mac = Self {
+            eth_mac,
+            mmc: Mmc::new(eth_mmc),
+            multicast_buckets: [0; 64],
+            miim_pending: None,
+        };
+
         // Frame filter register
-        eth_mac.macffr.modify(|_, w| {
-            // Receive All
-            w.ra()
-                .set_bit()
-                // Promiscuous mode
-                .pm()
-                .set_bit()
-        });
+        mac.set_filter_mode(config.filter_mode);
+
+        if let Some(mac_address) = config.mac_address {
+            mac.set_mac_address(mac_address);
+        }
+
+        mac.set_vlan_filter(config.vlan_filter);
 
         // Flow Control Register
-        eth_mac.macfcr.modify(|_, w| {
-            // Pause time
-            w.pt().bits(0x100)
-        });
+        mac.set_pause_time(config.pause_time);
+        mac.set_pause_low_threshold(config.pause_low_threshold);
+        mac.set_flow_control(config.flow_control);
+
+        Ok(mac)
+    }
 
-        // Disable all MMC RX interrupts
-        eth_mmc
-            .mmcrimr
-            .write(|w| w.rgufm().set_bit().rfaem().set_bit().rfcem().set_bit());
+    /// Set the speed at which the MAC transmits and receives.
+    ///
+    /// This must match the speed of the link that the PHY has
+    /// established. See [`update_link_from_phy`].
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.eth_mac
+            .maccr
+            .modify(|_, w| w.fes().bit(speed == Speed::Mbps100));
+    }
 
-        // Disable all MMC TX interrupts
-        eth_mmc
-            .mmctimr
-            .write(|w| w.tgfm().set_bit().tgfmscm().set_bit().tgfscm().set_bit());
+    /// Get the speed at which the MAC is currently configured.
+    pub fn speed(&self) -> Speed {
+        if self.eth_mac.maccr.read().fes().bit_is_set() {
+            Speed::Mbps100
+        } else {
+            Speed::Mbps10
+        }
+    }
 
-        // Fix incorrect TGFM bit position until https://github.com/stm32-rs/stm32-rs/pull/689
-        // is released and used by HALs.
-        eth_mmc
-            .mmctimr
-            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 21)) });
+    /// Set the duplex mode of the MAC.
+    ///
+    /// This must match the duplex mode of the link that the PHY has
+    /// established. See [`update_link_from_phy`].
+    pub fn set_duplex(&mut self, duplex: Duplex) {
+        self.eth_mac
+            .maccr
+            .modify(|_, w| w.dm().bit(duplex == Duplex::Full));
+    }
 
-        Ok(Self { eth_mac })
+    /// Get the duplex mode that the MAC is currently configured in.
+    pub fn duplex(&self) -> Duplex {
+        if self.eth_mac.maccr.read().dm().bit_is_set() {
+            Duplex::Full
+        } else {
+            Duplex::Half
+        }
     }
 
     /// Borrow access to the MAC's SMI.
@@ -217,6 +330,16 @@ where
     }
 }
 
+impl<MDIO, MDC> DerefMut for EthernetMACWithMii<MDIO, MDC>
+where
+    MDIO: MdioPin,
+    MDC: MdcPin,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.eth_mac
+    }
+}
+
 impl<MDIO, MDC> EthernetMACWithMii<MDIO, MDC>
 where
     MDIO: MdioPin,
@@ -235,6 +358,64 @@ where
             .mii(&mut self.mdio, &mut self.mdc)
             .write(phy, reg, data)
     }
+
+    /// Read MII register `reg` from the PHY at address `phy`, or return
+    /// [`MiimError::Timeout`] if the transaction does not complete in time.
+    pub fn try_read(&mut self, phy: u8, reg: u8) -> Result<u16, MiimError> {
+        self.eth_mac
+            .mii(&mut self.mdio, &mut self.mdc)
+            .try_read(phy, reg)
+    }
+
+    /// Write the value `data` to MII register `reg` to the PHY at address `phy`, or
+    /// return [`MiimError::Timeout`] if the transaction does not complete in time.
+    pub fn try_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        self.eth_mac
+            .mii(&mut self.mdio, &mut self.mdc)
+            .try_write(phy, reg, data)
+    }
+
+    /// Start reading MII register `reg` from the PHY at address `phy`.
+    ///
+    /// See [`Stm32Mii::start_read`].
+    pub fn start_read(&mut self, phy: u8, reg: u8) -> Result<(), MiimError> {
+        self.eth_mac
+            .mii(&mut self.mdio, &mut self.mdc)
+            .start_read(phy, reg)
+    }
+
+    /// Start writing the value `data` to MII register `reg` to the PHY at address `phy`.
+    ///
+    /// See [`Stm32Mii::start_write`].
+    pub fn start_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        self.eth_mac
+            .mii(&mut self.mdio, &mut self.mdc)
+            .start_write(phy, reg, data)
+    }
+
+    /// Get the value of the read that was started with
+    /// [`EthernetMACWithMii::start_read`].
+    ///
+    /// See [`Stm32Mii::poll_read`].
+    pub fn poll_read(&mut self) -> Result<u16, MiimError> {
+        self.eth_mac.mii(&mut self.mdio, &mut self.mdc).poll_read()
+    }
+
+    /// Check whether the write that was started with
+    /// [`EthernetMACWithMii::start_write`] has completed.
+    ///
+    /// See [`Stm32Mii::poll_write`].
+    pub fn poll_write(&mut self) -> Result<(), MiimError> {
+        self.eth_mac.mii(&mut self.mdio, &mut self.mdc).poll_write()
+    }
+
+    /// Probe all 32 addresses of the MDIO bus, and return the PHY
+    /// identifier of every PHY that responds.
+    ///
+    /// See [`scan`].
+    pub fn scan(&mut self) -> PhyScan {
+        scan(self)
+    }
 }
 
 impl<MDIO, MDC> miim::Miim for EthernetMACWithMii<MDIO, MDC>
@@ -250,3 +431,157 @@ where
         self.write(phy, reg, data)
     }
 }
+
+impl<MDIO, MDC> FallibleMiim for EthernetMACWithMii<MDIO, MDC>
+where
+    MDIO: MdioPin,
+    MDC: MdcPin,
+{
+    fn try_read(&mut self, phy: u8, reg: u8) -> Result<u16, MiimError> {
+        self.try_read(phy, reg)
+    }
+
+    fn try_write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), MiimError> {
+        self.try_write(phy, reg, data)
+    }
+}
+
+/// Read the basic status register of `phy`.
+///
+/// Returns `None` if the MDIO transaction fails, or if no PHY responds,
+/// in which case the register reads as all ones.
+fn read_bsr<M, P>(phy: &mut P) -> Option<u16>
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    let addr = phy.get_phy_addr();
+
+    match phy.get_miim().try_read(addr, PHY_REG_BSR) {
+        Ok(0xFFFF) | Err(_) => None,
+        Ok(bsr) => Some(bsr),
+    }
+}
+
+/// Whether the link of `phy` is up.
+///
+/// Unlike [`Phy::phy_link_up`], a missing PHY or a failed MDIO
+/// transaction is reported as a link that is down.
+pub(crate) fn phy_link_up<M, P>(phy: &mut P) -> bool
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    read_bsr(phy).map_or(false, |bsr| {
+        bsr & PHY_BSR_LINK_STATUS == PHY_BSR_LINK_STATUS
+    })
+}
+
+/// Read the speed and duplex mode of the link that `phy` has established.
+///
+/// If auto-negotiation is enabled, the result is the highest common
+/// ability that the PHY and its link partner have advertised. Otherwise,
+/// the manually selected speed and duplex mode of the PHY are returned.
+///
+/// Returns `None` if the link is down, if auto-negotiation has not
+/// completed (yet), or if the PHY does not respond.
+pub fn negotiated_link<M, P>(phy: &mut P) -> Option<(Speed, Duplex)>
+where
+    M: FallibleMiim,
+    P: Phy<M>,
+{
+    let bsr = read_bsr(phy)?;
+    if bsr & PHY_BSR_LINK_STATUS == 0 {
+        return None;
+    }
+
+    let addr = phy.get_phy_addr();
+    let miim = phy.get_miim();
+
+    let bcr = miim.try_read(addr, PHY_REG_BCR).ok()?;
+
+    if bcr & PHY_BCR_AN_ENABLE == 0 {
+        let speed = if bcr & PHY_BCR_SPEED == PHY_BCR_SPEED {
+            Speed::Mbps100
+        } else {
+            Speed::Mbps10
+        };
+
+        let duplex = if bcr & PHY_BCR_DUPLEX == PHY_BCR_DUPLEX {
+            Duplex::Full
+        } else {
+            Duplex::Half
+        };
+
+        return Some((speed, duplex));
+    }
+
+    if bsr & PHY_BSR_AN_COMPLETE == 0 {
+        return None;
+    }
+
+    let common =
+        miim.try_read(addr, PHY_REG_ANAR).ok()? & miim.try_read(addr, PHY_REG_ANLPAR).ok()?;
+
+    // Resolve the highest common denominator, in order of priority
+    // as specified by IEEE 802.3 Annex 28B.3
+    if common & PHY_AN_100BASE_TX_FD != 0 {
+        Some((Speed::Mbps100, Duplex::Full))
+    } else if common & (PHY_AN_100BASE_T4 | PHY_AN_100BASE_TX_HD) != 0 {
+        Some((Speed::Mbps100, Duplex::Half))
+    } else if common & PHY_AN_10BASE_T_FD != 0 {
+        Some((Speed::Mbps10, Duplex::Full))
+    } else if common & PHY_AN_10BASE_T_HD != 0 {
+        Some((Speed::Mbps10, Duplex::Half))
+    } else {
+        None
+    }
+}
+
+/// Configure the MAC that `phy` communicates through with the speed
+/// and duplex mode of the link that `phy` has established.
+///
+/// In full-duplex mode, the flow control that `phy` has negotiated (see
+/// [`negotiated_flow_control`]) is applied as well. In half-duplex mode,
+/// flow control is left unchanged, so that [back pressure](EthernetMAC::set_back_pressure)
+/// can stay applied.
+///
+/// This function should be called whenever the link comes up, as
+/// the MAC does not follow the link parameters of the PHY by itself.
+/// Calling it while the link is already up is harmless.
+///
+/// Returns the applied speed and duplex mode, or `None` if the link
+/// is down (in which case the MAC is left untouched).
+///
+/// ```no_run
+/// # use stm32_eth::mac::{phy::BarePhy, EthernetMACWithMii, MdioPin, MdcPin};
+/// # fn poll<MDIO: MdioPin, MDC: MdcPin>(phy: &mut BarePhy<EthernetMACWithMii<MDIO, MDC>>) {
+/// if let Some((speed, duplex)) = stm32_eth::mac::update_link_from_phy(phy) {
+///     // The MAC now matches the link
+/// }
+/// # }
+/// ```
+pub fn update_link_from_phy<M, P>(phy: &mut P) -> Option<(Speed, Duplex)>
+where
+    M: FallibleMiim + DerefMut<Target = EthernetMAC>,
+    P: Phy<M>,
+{
+    let (speed, duplex) = negotiated_link(phy)?;
+
+    // In half-duplex mode, transmit flow control only determines whether
+    // back pressure can be applied, which is up to the user.
+    let flow_control = if duplex == Duplex::Full {
+        Some(negotiated_flow_control(phy).unwrap_or(FlowControl::NONE))
+    } else {
+        None
+    };
+
+    let mac = phy.get_miim().deref_mut();
+    mac.set_speed(speed);
+    mac.set_duplex(duplex);
+    if let Some(flow_control) = flow_control {
+        mac.set_flow_control(flow_control);
+    }
+
+    Some((speed, duplex))
+}
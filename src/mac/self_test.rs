@@ -0,0 +1,438 @@
+use crate::{EthernetDMA, RxError, RxPacket};
+
+use super::EthernetMAC;
+
+/// Loopback mode
+const MACCR_LM: u32 = 1 << 12;
+/// Duplex mode
+const MACCR_DM: u32 = 1 << 11;
+
+/// The EtherType of the test pattern frames (local experimental EtherType 1)
+const ETHER_TYPE_TEST: u16 = 0x88B5;
+/// The EtherType of IPv4
+const ETHER_TYPE_IPV4: u16 = 0x0800;
+
+/// The lengths of the test pattern frames, excluding their CRC.
+const PATTERN_LENGTHS: [usize; 7] = [60, 61, 64, 127, 256, 1024, 1514];
+
+/// The amount of times that the DMA is polled for a free TX entry or a
+/// looped back frame, before the frame is considered lost.
+const POLL_ATTEMPTS: u32 = 100_000;
+
+/// The layout of the IPv4/UDP frame that is used to test checksum offloading.
+const IP_OFFSET: usize = 14;
+const IP_HEADER_LEN: usize = 20;
+const UDP_OFFSET: usize = IP_OFFSET + IP_HEADER_LEN;
+const UDP_LEN: usize = 8 + 32;
+const CHECKSUM_FRAME_LEN: usize = UDP_OFFSET + UDP_LEN;
+/// The IPv4 address that is used as the source and destination
+/// of the checksum test frame (link-local).
+const IP_ADDRESS: [u8; 4] = [169, 254, 0, 1];
+/// The UDP port that is used as the source and destination
+/// of the checksum test frame (discard).
+const UDP_PORT: u16 = 9;
+
+/// The results of an [`EthernetMAC::self_test`].
+///
+/// Every test pattern frame is counted in exactly one of `passed`,
+/// `lost`, `length_mismatches`, `content_mismatches` and `rx_errors`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SelfTestReport {
+    /// Test pattern frames that were sent through the loopback.
+    pub frames: u32,
+    /// Frames that were received back intact.
+    pub passed: u32,
+    /// Frames that were not received back, or that could not be
+    /// sent because the TX ring did not become available.
+    pub lost: u32,
+    /// Frames that were received back with a different length.
+    pub length_mismatches: u32,
+    /// Frames that were received back with different contents.
+    pub content_mismatches: u32,
+    /// Frames that were received back as truncated or
+    /// erroneous (see [`RxError`]).
+    pub rx_errors: u32,
+    /// Whether the IPv4 header and UDP checksums that were inserted by the
+    /// MAC are correct, or `None` if TX checksum insertion is disabled.
+    pub checksum_insertion: Option<bool>,
+    /// Whether the MAC accepted a frame with correct checksums and, if TX
+    /// checksum insertion is disabled, flagged a frame with an incorrect IPv4
+    /// header checksum. `None` if RX checksum offload is disabled.
+    pub checksum_verification: Option<bool>,
+}
+
+impl SelfTestReport {
+    /// Whether all test pattern frames were received back intact, and
+    /// checksum offloading works as configured.
+    pub fn is_ok(&self) -> bool {
+        self.frames > 0
+            && self.passed == self.frames
+            && self.checksum_insertion != Some(false)
+            && self.checksum_verification != Some(false)
+    }
+}
+
+/// The result of comparing a looped back frame to the test pattern.
+enum Comparison {
+    Equal,
+    LengthMismatch,
+    ContentMismatch,
+}
+
+/// The outcome of waiting for a looped back frame.
+enum Received<R> {
+    Frame(R),
+    Error(RxError),
+    Lost,
+}
+
+impl EthernetMAC {
+    /// Enable or disable the internal loopback mode of the MAC.
+    ///
+    /// In loopback mode, transmitted frames are looped back into the receive
+    /// path of the MAC. The MAC still requires the RX and TX clocks of the
+    /// MII/RMII interface, but does not need a link.
+    pub fn set_loopback(&mut self, enable: bool) {
+        self.eth_mac.maccr.modify(|r, w| unsafe {
+            if enable {
+                w.bits(r.bits() | MACCR_LM)
+            } else {
+                w.bits(r.bits() & !MACCR_LM)
+            }
+        });
+    }
+
+    /// Whether the internal loopback mode of the MAC is enabled.
+    pub fn loopback(&self) -> bool {
+        (self.eth_mac.maccr.read().bits() & MACCR_LM) == MACCR_LM
+    }
+
+    /// Test the MAC and the DMA by sending frames through the
+    /// internal loopback, and verifying the frames that are received back.
+    ///
+    /// Test pattern frames of various lengths (up to 1514 bytes, as long
+    /// as they fit in the buffers of the TX ring) are sent and received
+    /// one at a time, and their length and contents are compared. If checksum
+    /// offloading is enabled, an IPv4/UDP frame is sent to verify that the
+    /// checksums are inserted and verified by the MAC.
+    ///
+    /// The MAC is switched to loopback mode and full duplex for the duration
+    /// of the test, and frames that are received before or during the test are
+    /// discarded. The frames are sent to the [station address](EthernetMAC::mac_address),
+    /// so they must not be dropped by the frame filter or the
+    /// [VLAN-only](EthernetDMA::set_vlan_only) setting of the DMA. The test frames
+    /// are included in the [statistics](EthernetDMA::statistics).
+    ///
+    /// Waiting for the DMA is bounded by a fixed amount of polls, so this
+    /// function does not block forever if frames are lost.
+    pub fn self_test<const N: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, N>,
+    ) -> SelfTestReport {
+        let maccr = self.eth_mac.maccr.read().bits();
+        self.eth_mac
+            .maccr
+            .write(|w| unsafe { w.bits(maccr | MACCR_LM | MACCR_DM) });
+
+        let report = self.loopback_test(dma);
+
+        self.eth_mac.maccr.write(|w| unsafe { w.bits(maccr) });
+
+        report
+    }
+
+    /// Send the test frames of [`EthernetMAC::self_test`] through a
+    /// loopback that has already been set up, and verify them.
+    pub(super) fn loopback_test<const N: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, N>,
+    ) -> SelfTestReport {
+        drain(dma);
+
+        let address = self.mac_address();
+        let mut report = SelfTestReport::default();
+
+        for (index, &length) in PATTERN_LENGTHS.iter().enumerate() {
+            if length > N {
+                continue;
+            }
+
+            let index = index as u8;
+            report.frames += 1;
+
+            if !send(dma, length, |frame| write_pattern(frame, &address, index)) {
+                report.lost += 1;
+                continue;
+            }
+
+            let received = receive(dma, |packet| {
+                // The CRC is included if CRC stripping is disabled.
+                if packet.frame_len() != length && packet.frame_len() != length + 4 {
+                    return Comparison::LengthMismatch;
+                }
+
+                let mut offset = 0;
+                let matches = packet
+                    .segments()
+                    .flat_map(|segment| segment.iter())
+                    .take(length)
+                    .all(|&byte| {
+                        let expected = pattern_byte(&address, index, offset);
+                        offset += 1;
+                        byte == expected
+                    });
+
+                if matches {
+                    Comparison::Equal
+                } else {
+                    Comparison::ContentMismatch
+                }
+            });
+
+            match received {
+                Received::Frame(Comparison::Equal) => report.passed += 1,
+                Received::Frame(Comparison::LengthMismatch) => report.length_mismatches += 1,
+                Received::Frame(Comparison::ContentMismatch) => report.content_mismatches += 1,
+                Received::Error(_) => report.rx_errors += 1,
+                Received::Lost => report.lost += 1,
+            }
+        }
+
+        let insertion = dma.tx_checksum_insertion();
+        let verification = dma.rx_checksum_offload();
+
+        if (insertion || verification) && CHECKSUM_FRAME_LEN <= N {
+            // The checksums are calculated in software if they are not inserted.
+            let received = if send(dma, CHECKSUM_FRAME_LEN, |frame| {
+                write_udp_frame(frame, &address, !insertion)
+            }) {
+                receive(dma, |packet| {
+                    let mut frame = [0; CHECKSUM_FRAME_LEN];
+                    packet.copy_into(&mut frame);
+                    checksums_are_valid(&frame)
+                })
+            } else {
+                Received::Lost
+            };
+
+            if insertion {
+                report.checksum_insertion = Some(matches!(received, Received::Frame(true)));
+            }
+
+            if verification {
+                let accepted = matches!(received, Received::Frame(_));
+
+                // A frame with an incorrect checksum can only be sent if
+                // the checksums are not inserted by the MAC.
+                let rejected = insertion
+                    || send(dma, CHECKSUM_FRAME_LEN, |frame| {
+                        write_udp_frame(frame, &address, true);
+                        frame[IP_OFFSET + 10] ^= 0xFF;
+                    }) && matches!(
+                        receive(dma, |_| ()),
+                        Received::Error(RxError::DmaError(errors)) if errors.ip_header_error
+                    );
+
+                report.checksum_verification = Some(accepted && rejected);
+            }
+        }
+
+        drain(dma);
+
+        report
+    }
+}
+
+/// Discard all frames that are waiting in the RX ring.
+fn drain<const N: usize>(dma: &mut EthernetDMA<'_, '_, N>) {
+    for _ in 0..POLL_ATTEMPTS {
+        if let Err(RxError::WouldBlock) = dma.recv_next() {
+            break;
+        }
+    }
+}
+
+/// Send a frame of `length` bytes that is written by `f`, waiting
+/// for an entry in the TX ring if necessary.
+fn send<const N: usize, F>(dma: &mut EthernetDMA<'_, '_, N>, length: usize, f: F) -> bool
+where
+    F: Fn(&mut [u8]) + Copy,
+{
+    (0..POLL_ATTEMPTS).any(|_| dma.send(length, f).is_ok())
+}
+
+/// Wait for the next received frame, and inspect it with `f`.
+fn receive<const N: usize, R>(
+    dma: &mut EthernetDMA<'_, '_, N>,
+    f: impl FnOnce(&RxPacket<N>) -> R,
+) -> Received<R> {
+    for _ in 0..POLL_ATTEMPTS {
+        match dma.recv_next() {
+            Ok(packet) => return Received::Frame(f(&packet)),
+            Err(RxError::WouldBlock) => {}
+            Err(e) => return Received::Error(e),
+        }
+    }
+
+    Received::Lost
+}
+
+/// The byte at `offset` in test pattern frame `index`.
+fn pattern_byte(address: &[u8; 6], index: u8, offset: usize) -> u8 {
+    match offset {
+        0..=5 => address[offset],
+        6..=11 => address[offset - 6],
+        12..=13 => ETHER_TYPE_TEST.to_be_bytes()[offset - 12],
+        _ => (offset as u8).wrapping_mul(31) ^ index.wrapping_mul(0x5B),
+    }
+}
+
+fn write_pattern(frame: &mut [u8], address: &[u8; 6], index: u8) {
+    for (offset, byte) in frame.iter_mut().enumerate() {
+        *byte = pattern_byte(address, index, offset);
+    }
+}
+
+/// Write an IPv4/UDP frame, and calculate its checksums
+/// if `with_checksums` is set.
+fn write_udp_frame(frame: &mut [u8], address: &[u8; 6], with_checksums: bool) {
+    frame[0..6].copy_from_slice(address);
+    frame[6..12].copy_from_slice(address);
+    frame[12..14].copy_from_slice(&ETHER_TYPE_IPV4.to_be_bytes());
+
+    let ip = &mut frame[IP_OFFSET..UDP_OFFSET];
+    // Version 4, header length of 5 words
+    ip[0] = 0x45;
+    ip[1] = 0;
+    ip[2..4].copy_from_slice(&((IP_HEADER_LEN + UDP_LEN) as u16).to_be_bytes());
+    // Identification 0, don't fragment
+    ip[4..8].copy_from_slice(&[0, 0, 0x40, 0]);
+    // TTL 64, protocol UDP
+    ip[8] = 64;
+    ip[9] = 17;
+    ip[10..12].copy_from_slice(&[0, 0]);
+    ip[12..16].copy_from_slice(&IP_ADDRESS);
+    ip[16..20].copy_from_slice(&IP_ADDRESS);
+
+    let udp = &mut frame[UDP_OFFSET..CHECKSUM_FRAME_LEN];
+    udp[0..2].copy_from_slice(&UDP_PORT.to_be_bytes());
+    udp[2..4].copy_from_slice(&UDP_PORT.to_be_bytes());
+    udp[4..6].copy_from_slice(&(UDP_LEN as u16).to_be_bytes());
+    udp[6..8].copy_from_slice(&[0, 0]);
+    for (i, byte) in udp[8..].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    if with_checksums {
+        let ip_checksum = !fold(sum(0, &frame[IP_OFFSET..UDP_OFFSET]));
+        frame[IP_OFFSET + 10..IP_OFFSET + 12].copy_from_slice(&ip_checksum.to_be_bytes());
+
+        let udp_checksum = !fold(udp_sum(frame));
+        frame[UDP_OFFSET + 6..UDP_OFFSET + 8].copy_from_slice(&udp_checksum.to_be_bytes());
+    }
+}
+
+/// Whether the IPv4 header and UDP checksums of a
+/// checksum test frame are correct.
+fn checksums_are_valid(frame: &[u8; CHECKSUM_FRAME_LEN]) -> bool {
+    let ip_valid = fold(sum(0, &frame[IP_OFFSET..UDP_OFFSET])) == 0xFFFF;
+
+    // A UDP checksum of 0 means that no checksum was calculated.
+    let udp_valid =
+        frame[UDP_OFFSET + 6..UDP_OFFSET + 8] != [0, 0] && fold(udp_sum(frame)) == 0xFFFF;
+
+    ip_valid && udp_valid
+}
+
+/// The one's complement sum of the UDP pseudo-header and datagram.
+fn udp_sum(frame: &[u8]) -> u32 {
+    let pseudo_header = sum(0, &frame[IP_OFFSET + 12..IP_OFFSET + 20]) + 17 + UDP_LEN as u32;
+    sum(pseudo_header, &frame[UDP_OFFSET..CHECKSUM_FRAME_LEN])
+}
+
+/// Add the big-endian 16-bit words of `data` to `initial`.
+fn sum(initial: u32, data: &[u8]) -> u32 {
+    data.chunks(2).fold(initial, |sum, word| {
+        sum + u32::from(u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)]))
+    })
+}
+
+/// Fold a sum into a 16-bit one's complement sum.
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    fn udp_frame(with_checksums: bool) -> [u8; CHECKSUM_FRAME_LEN] {
+        let mut frame = [0; CHECKSUM_FRAME_LEN];
+        write_udp_frame(&mut frame, &ADDRESS, with_checksums);
+        frame
+    }
+
+    #[test]
+    fn udp_frame_checksums() {
+        assert!(checksums_are_valid(&udp_frame(true)));
+
+        // Without checksums, the UDP checksum is left at 0.
+        assert!(!checksums_are_valid(&udp_frame(false)));
+    }
+
+    #[test]
+    fn corrupted_udp_frame() {
+        // The IPv4 header checksum, as the self test corrupts it.
+        let mut frame = udp_frame(true);
+        frame[IP_OFFSET + 10] ^= 0xFF;
+        assert!(!checksums_are_valid(&frame));
+
+        // The TTL, which is only covered by the IPv4 header checksum.
+        let mut frame = udp_frame(true);
+        frame[IP_OFFSET + 8] ^= 0x01;
+        assert!(!checksums_are_valid(&frame));
+
+        // The UDP payload.
+        let mut frame = udp_frame(true);
+        frame[CHECKSUM_FRAME_LEN - 1] ^= 0x01;
+        assert!(!checksums_are_valid(&frame));
+    }
+
+    #[test]
+    fn one_complement_sum() {
+        // The example of RFC 1071.
+        let data = [0x00, 0x01, 0xF2, 0x03, 0xF4, 0xF5, 0xF6, 0xF7];
+        assert_eq!(sum(0, &data), 0x2_DDF0);
+        assert_eq!(fold(sum(0, &data)), 0xDDF2);
+
+        // An odd trailing byte is padded with zero.
+        assert_eq!(sum(1, &[0x12, 0x34, 0x56]), 0x1234 + 0x5600 + 1);
+        assert_eq!(fold(0xFFFF_FFFF), 0xFFFF);
+    }
+
+    #[test]
+    fn pattern_frame() {
+        let mut frame = [0; 64];
+        write_pattern(&mut frame, &ADDRESS, 3);
+
+        assert_eq!(frame[0..6], ADDRESS);
+        assert_eq!(frame[6..12], ADDRESS);
+        assert_eq!(frame[12..14], ETHER_TYPE_TEST.to_be_bytes());
+        assert!(frame
+            .iter()
+            .enumerate()
+            .all(|(offset, &byte)| byte == pattern_byte(&ADDRESS, 3, offset)));
+
+        // Frames with different indices have different payloads.
+        let mut other = [0; 64];
+        write_pattern(&mut other, &ADDRESS, 4);
+        assert_eq!(frame[..14], other[..14]);
+        assert_ne!(frame[14..], other[14..]);
+    }
+}
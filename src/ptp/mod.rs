@@ -0,0 +1,234 @@
+//! IEEE 1588 precision time protocol (PTP) hardware clock
+//!
+//! The PTP peripheral keeps a system time that is used to timestamp
+//! received and transmitted frames. Once an [`EthernetPTP`] has been created,
+//! the timestamp of a received frame can be obtained with
+//! [`RxPacket::timestamp`](crate::RxPacket::timestamp), and the timestamp
+//! of a transmitted frame with [`EthernetDMA::tx_timestamp`](crate::EthernetDMA::tx_timestamp).
+//!
+//! The system time runs in digital rollover mode: the subseconds
+//! part of the system time counts nanoseconds.
+
+use crate::{hal::rcc::Clocks, stm32::ETHERNET_PTP, EthernetDMA};
+
+mod timestamp;
+pub use timestamp::Timestamp;
+
+use timestamp::NANOS_PER_SECOND;
+
+/// Timestamp enable
+const PTPTSCR_TSE: u32 = 1 << 0;
+/// Timestamp fine or coarse update
+const PTPTSCR_TSFCU: u32 = 1 << 1;
+/// Timestamp system time initialize
+const PTPTSCR_TSSTI: u32 = 1 << 2;
+/// Timestamp system time update
+const PTPTSCR_TSSTU: u32 = 1 << 3;
+/// Timestamp addend register update
+const PTPTSCR_TSARU: u32 = 1 << 5;
+/// Timestamp snapshot for all received frames enable
+const PTPTSCR_TSSARFE: u32 = 1 << 8;
+/// Timestamp subsecond rollover: digital or binary rollover control
+const PTPTSCR_TSSSR: u32 = 1 << 9;
+
+/// Add or subtract time
+const PTPTSLUR_ADDSUB: u32 = 1 << 31;
+
+/// The amount of times that the timestamp control register is polled
+/// before an update of the system time or the addend is considered to
+/// have timed out. An update completes within a few HCLK cycles.
+const PTP_WAIT_ATTEMPTS: u32 = 100_000;
+
+/// Errors that can occur when updating the PTP hardware clock.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtpError {
+    /// The update of the system time or the addend did not complete
+    /// in time. This happens if the PTP clock is not running.
+    Timeout,
+}
+
+/// Ethernet PTP hardware clock.
+pub struct EthernetPTP {
+    eth_ptp: ETHERNET_PTP,
+    base_addend: u32,
+}
+
+impl EthernetPTP {
+    /// Create and initialise the PTP hardware clock.
+    ///
+    /// The system time is initialised to zero, and timestamping of all
+    /// received and transmitted frames is enabled.
+    ///
+    /// The DMA must be initialised before the PTP hardware clock, as
+    /// the enhanced descriptors that it sets up are used to store
+    /// the timestamps of frames.
+    ///
+    /// Returns [`PtpError::Timeout`] if the PTP clock does not respond.
+    pub fn new<const N: usize>(
+        eth_ptp: ETHERNET_PTP,
+        clocks: Clocks,
+        _dma: &EthernetDMA<'_, '_, N>,
+    ) -> Result<Self, PtpError> {
+        let hclk = clocks.hclk().to_Hz();
+
+        // The subsecond increment is chosen so that the accumulator
+        // overflows at most at half of the HCLK frequency, which
+        // leaves room for frequency adjustments through the addend.
+        let subsecond_increment =
+            ((2 * NANOS_PER_SECOND as u64 + hclk as u64 - 1) / hclk as u64).max(1) as u32;
+
+        // The accumulator must overflow once every `subsecond_increment`
+        // nanoseconds: addend = 2^32 * (10^9 / increment) / hclk
+        let base_addend = (((1u64 << 32) * NANOS_PER_SECOND as u64)
+            / (subsecond_increment as u64 * hclk as u64)) as u32;
+
+        // Enable timestamping
+        eth_ptp
+            .ptptscr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PTPTSCR_TSE) });
+
+        // Program the subsecond increment
+        eth_ptp
+            .ptpssir
+            .write(|w| unsafe { w.bits(subsecond_increment) });
+
+        let mut me = Self {
+            eth_ptp,
+            base_addend,
+        };
+
+        me.set_addend(base_addend)?;
+
+        me.eth_ptp.ptptscr.modify(|r, w| unsafe {
+            w.bits(
+                r.bits()
+                    // Fine update
+                    | PTPTSCR_TSFCU
+                    // Timestamp all received frames
+                    | PTPTSCR_TSSARFE
+                    // Digital rollover
+                    | PTPTSCR_TSSSR,
+            )
+        });
+
+        // Initialise the system time
+        me.eth_ptp.ptptshur.write(|w| unsafe { w.bits(0) });
+        me.eth_ptp.ptptslur.write(|w| unsafe { w.bits(0) });
+        me.eth_ptp
+            .ptptscr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PTPTSCR_TSSTI) });
+        me.wait_for(PTPTSCR_TSSTI)?;
+
+        Ok(me)
+    }
+
+    /// Get the current system time.
+    pub fn get_time(&self) -> Timestamp {
+        // Read the seconds twice, in case they changed
+        // while reading the subseconds.
+        loop {
+            let high = self.eth_ptp.ptptshr.read().bits();
+            let low = self.eth_ptp.ptptslr.read().bits();
+
+            if high == self.eth_ptp.ptptshr.read().bits() {
+                return Timestamp::from_parts(high, low);
+            }
+        }
+    }
+
+    /// Set the current system time.
+    pub fn set_time(&mut self, time: Timestamp) -> Result<(), PtpError> {
+        self.wait_for_update()?;
+
+        self.eth_ptp
+            .ptptshur
+            .write(|w| unsafe { w.bits(time.seconds()) });
+        self.eth_ptp
+            .ptptslur
+            .write(|w| unsafe { w.bits(time.nanos()) });
+
+        self.eth_ptp
+            .ptptscr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PTPTSCR_TSSTI) });
+        self.wait_for_update()
+    }
+
+    /// Step the current system time forwards (positive `offset_nanos`)
+    /// or backwards (negative `offset_nanos`).
+    pub fn step(&mut self, offset_nanos: i64) -> Result<(), PtpError> {
+        let negative = offset_nanos < 0;
+        let offset = Timestamp::from_nanos(offset_nanos.unsigned_abs());
+
+        let low = if negative {
+            offset.nanos() | PTPTSLUR_ADDSUB
+        } else {
+            offset.nanos()
+        };
+
+        self.wait_for_update()?;
+
+        self.eth_ptp
+            .ptptshur
+            .write(|w| unsafe { w.bits(offset.seconds()) });
+        self.eth_ptp.ptptslur.write(|w| unsafe { w.bits(low) });
+
+        self.eth_ptp
+            .ptptscr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PTPTSCR_TSSTU) });
+        self.wait_for_update()
+    }
+
+    /// Get the value of the addend register.
+    pub fn addend(&self) -> u32 {
+        self.eth_ptp.ptptsar.read().bits()
+    }
+
+    /// The addend value for which the system time runs at the
+    /// nominal rate, assuming that HCLK is exact.
+    pub fn base_addend(&self) -> u32 {
+        self.base_addend
+    }
+
+    /// Set the value of the addend register.
+    ///
+    /// The system time runs faster if the addend is increased, and slower
+    /// if it is decreased. See [`EthernetPTP::adjust_frequency`] to adjust
+    /// the rate of the system time in parts per billion.
+    pub fn set_addend(&mut self, addend: u32) -> Result<(), PtpError> {
+        self.eth_ptp.ptptsar.write(|w| unsafe { w.bits(addend) });
+
+        self.eth_ptp
+            .ptptscr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PTPTSCR_TSARU) });
+        self.wait_for(PTPTSCR_TSARU)
+    }
+
+    /// Adjust the rate of the system time by `ppb` parts per
+    /// billion, relative to the [base addend](EthernetPTP::base_addend).
+    ///
+    /// A positive value makes the system time run faster.
+    pub fn adjust_frequency(&mut self, ppb: i32) -> Result<(), PtpError> {
+        let base_addend = self.base_addend as i64;
+        let addend = base_addend + (base_addend * ppb as i64) / NANOS_PER_SECOND as i64;
+        let addend = addend.max(0).min(u32::MAX as i64) as u32;
+
+        self.set_addend(addend)
+    }
+
+    /// Wait until an ongoing initialisation or update
+    /// of the system time has completed.
+    fn wait_for_update(&self) -> Result<(), PtpError> {
+        self.wait_for(PTPTSCR_TSSTI | PTPTSCR_TSSTU)
+    }
+
+    /// Wait until the self-clearing `bits` of the timestamp
+    /// control register have been cleared.
+    fn wait_for(&self, bits: u32) -> Result<(), PtpError> {
+        if (0..PTP_WAIT_ATTEMPTS).any(|_| self.eth_ptp.ptptscr.read().bits() & bits == 0) {
+            Ok(())
+        } else {
+            Err(PtpError::Timeout)
+        }
+    }
+}
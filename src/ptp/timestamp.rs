@@ -0,0 +1,63 @@
+/// The amount of nanoseconds in a second.
+pub(crate) const NANOS_PER_SECOND: u32 = 1_000_000_000;
+
+/// A timestamp produced by the PTP peripheral.
+///
+/// The system time of the PTP peripheral is kept in seconds and
+/// nanoseconds.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    seconds: u32,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// Create a new [`Timestamp`], or `None` if `nanos` is
+    /// not less than one second.
+    pub const fn new(seconds: u32, nanos: u32) -> Option<Self> {
+        if nanos < NANOS_PER_SECOND {
+            Some(Self { seconds, nanos })
+        } else {
+            None
+        }
+    }
+
+    /// Create a new [`Timestamp`] from a total amount of nanoseconds.
+    ///
+    /// The seconds part of the timestamp wraps around if `nanos`
+    /// is more than `u32::MAX` seconds.
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Self {
+            seconds: (nanos / NANOS_PER_SECOND as u64) as u32,
+            nanos: (nanos % NANOS_PER_SECOND as u64) as u32,
+        }
+    }
+
+    /// The seconds part of this timestamp.
+    pub const fn seconds(&self) -> u32 {
+        self.seconds
+    }
+
+    /// The nanoseconds part of this timestamp.
+    pub const fn nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// The total amount of nanoseconds in this timestamp.
+    pub const fn total_nanos(&self) -> u64 {
+        self.seconds as u64 * NANOS_PER_SECOND as u64 + self.nanos as u64
+    }
+
+    /// Create a timestamp from the values of the high (seconds)
+    /// and low (subseconds) timestamp words of a descriptor or
+    /// of the system time registers.
+    pub(crate) fn from_parts(high: u32, low: u32) -> Self {
+        // Bit 31 of the low word is the sign bit, which is
+        // never set for timestamps.
+        Self {
+            seconds: high,
+            nanos: low & 0x7FFF_FFFF,
+        }
+    }
+}
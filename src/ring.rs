@@ -1,19 +1,102 @@
 use aligned::{Aligned, A8};
 use core::ops::{Deref, DerefMut};
 
-use crate::{RxDescriptor, TxDescriptor, MTU};
+use crate::{EthernetConfig, RxDescriptor, TxDescriptor, MTU};
+
+#[cfg(test)]
+pub(crate) mod sim;
+
+/// The default size of the buffer of a [`RingEntry`]: the maximum
+/// size of a VLAN frame, rounded up to a multiple of 4 bytes.
+pub const DEFAULT_BUFFER_SIZE: usize = (MTU + 3) & !3;
+
+/// The largest buffer size that fits in the 13-bit buffer
+/// size field of a descriptor.
+const MAX_BUFFER_SIZE: usize = 0x1FFF;
 
 pub trait RingDescriptor {
     fn setup(&mut self, buffer: *const u8, len: usize, next: Option<&Self>);
 }
 
+/// Access to the registers of the ethernet DMA that are used by
+/// the RX and TX rings, and to restart the DMA.
+///
+/// This is implemented by the registers of the DMA peripheral,
+/// and by a simulated DMA engine in tests.
+pub trait DmaRegisters {
+    /// Register the address of the first descriptor of the RX ring.
+    fn set_rx_descriptor_list(&self, address: *const RxDescriptor);
+
+    /// Start the RX DMA process.
+    fn start_rx(&self);
+
+    /// Demand that the RX DMA polls the current descriptor.
+    fn demand_rx_poll(&self);
+
+    /// The state of the RX DMA process (the `RPS` field of `DMASR`).
+    fn rx_process_state(&self) -> u8;
+
+    /// Read the missed frame and buffer overflow counters
+    /// (`DMAMFBOCR`), which clears them.
+    fn read_missed_frames(&self) -> u32;
+
+    /// Register the address of the first descriptor of the TX ring.
+    fn set_tx_descriptor_list(&self, address: *const TxDescriptor);
+
+    /// Start the TX DMA process.
+    fn start_tx(&self);
+
+    /// Demand that the TX DMA polls the current descriptor.
+    fn demand_tx_poll(&self);
+
+    /// The state of the TX DMA process (the `TPS` field of `DMASR`).
+    fn tx_process_state(&self) -> u8;
+
+    /// Stop the RX DMA process.
+    fn stop_rx(&self);
+
+    /// Stop the TX DMA process.
+    fn stop_tx(&self);
+
+    /// Start flushing the TX FIFO.
+    fn flush_tx_fifo(&self);
+
+    /// Whether the TX FIFO is still being flushed.
+    fn tx_fifo_flushing(&self) -> bool;
+
+    /// Start a software reset of the DMA (`DMABMR.SR`).
+    fn software_reset(&self);
+
+    /// Whether the software reset is still in progress.
+    fn resetting(&self) -> bool;
+
+    /// Write the bus mode and operation mode registers
+    /// (`DMABMR` and `DMAOMR`) according to `config`.
+    fn configure(&self, config: &EthernetConfig);
+
+    /// The enabled interrupts (`DMAIER`).
+    fn interrupt_enable(&self) -> u32;
+
+    /// Set the enabled interrupts (`DMAIER`).
+    fn set_interrupt_enable(&self, bits: u32);
+
+    /// Clear all status bits in `DMASR`.
+    fn clear_status(&self);
+}
+
 /// An entry in a DMA Descriptor ring
-pub struct RingEntry<T: Clone + RingDescriptor> {
+///
+/// Every entry contains a buffer of `N` bytes. `N` must be a multiple
+/// of 4, and may not exceed 8191 bytes.
+///
+/// Received frames that do not fit in a single buffer are spread across
+/// multiple entries, but transmitted frames must fit in a single buffer.
+pub struct RingEntry<T: Clone + RingDescriptor, const N: usize = DEFAULT_BUFFER_SIZE> {
     desc: Aligned<A8, [T; 1]>,
-    buffer: Aligned<A8, [u8; MTU]>,
+    buffer: Aligned<A8, [u8; N]>,
 }
 
-impl<T: Clone + RingDescriptor> Clone for RingEntry<T> {
+impl<T: Clone + RingDescriptor, const N: usize> Clone for RingEntry<T, N> {
     fn clone(&self) -> Self {
         RingEntry {
             desc: Aligned((*self.desc).clone()),
@@ -22,16 +105,16 @@ impl<T: Clone + RingDescriptor> Clone for RingEntry<T> {
     }
 }
 
-impl<T: Clone + RingDescriptor + Default> Default for RingEntry<T> {
+impl<T: Clone + RingDescriptor + Default, const N: usize> Default for RingEntry<T, N> {
     fn default() -> Self {
         RingEntry {
             desc: Aligned([T::default()]),
-            buffer: Aligned([0; MTU]),
+            buffer: Aligned([0; N]),
         }
     }
 }
 
-impl RingEntry<TxDescriptor> {
+impl<const N: usize> RingEntry<TxDescriptor, N> {
     /// The initial value of a TxRingDescriptor
     pub const INIT: Self = Self::new();
 
@@ -39,12 +122,12 @@ impl RingEntry<TxDescriptor> {
     pub const fn new() -> Self {
         RingEntry {
             desc: Aligned([TxDescriptor::new()]),
-            buffer: Aligned([0; MTU]),
+            buffer: Aligned([0; N]),
         }
     }
 }
 
-impl RingEntry<RxDescriptor> {
+impl<const N: usize> RingEntry<RxDescriptor, N> {
     /// The initial value of an RxRingDescriptor
     pub const INIT: Self = Self::new();
 
@@ -52,13 +135,23 @@ impl RingEntry<RxDescriptor> {
     pub const fn new() -> Self {
         RingEntry {
             desc: Aligned([RxDescriptor::new()]),
-            buffer: Aligned([0; MTU]),
+            buffer: Aligned([0; N]),
         }
     }
 }
 
-impl<T: Clone + RingDescriptor> RingEntry<T> {
+impl<T: Clone + RingDescriptor, const N: usize> RingEntry<T, N> {
+    /// Fails to compile if the buffer size `N` is not supported.
+    const BUFFER_SIZE_CHECK: () = assert!(
+        N <= MAX_BUFFER_SIZE && N % 4 == 0,
+        "The buffer size of a RingEntry must be a multiple of 4, and at most 8191 bytes"
+    );
+
     pub(crate) fn setup(&mut self, next: Option<&Self>) {
+        // Fail to compile if the buffer size is not supported.
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::BUFFER_SIZE_CHECK;
+
         let buffer = self.buffer.as_ptr();
         let len = self.buffer.len();
         self.desc_mut()
@@ -0,0 +1,426 @@
+//! A software model of the ethernet DMA, which drives the
+//! [`RxRing`](crate::rx::RxRing) and [`TxRing`](crate::tx::TxRing)
+//! in tests on the host.
+//!
+//! The model owns the descriptors that the rings pass to it, writes
+//! received frames and status bits into them, and follows the chained
+//! descriptor addresses and the end-of-ring flags like the DMA does.
+//!
+//! The descriptors only hold the lower 32 bits of the buffer and next
+//! descriptor addresses. The model takes the upper bits from the address
+//! of the descriptor list, so the rings may not cross a 4 GiB boundary.
+
+use core::cell::{Cell, RefCell};
+
+use crate::{EthernetConfig, RxDescriptor, TxDescriptor};
+
+use super::DmaRegisters;
+
+/// Owned by DMA engine
+const DESC_0_OWN: u32 = 1 << 31;
+
+/// Error summary
+const RXDESC_0_ES: u32 = 1 << 15;
+/// Descriptor error
+const RXDESC_0_DE: u32 = 1 << 14;
+/// First descriptor
+const RXDESC_0_FS: u32 = 1 << 9;
+/// Last descriptor
+const RXDESC_0_LS: u32 = 1 << 8;
+/// Frame length
+const RXDESC_0_FL_SHIFT: u32 = 16;
+/// The errors that are included in the error summary: overflow, late
+/// collision, watchdog timeout, receive error and CRC error.
+const RXDESC_0_ES_ERRORS: u32 = (1 << 11) | (1 << 6) | (1 << 4) | (1 << 3) | (1 << 1);
+/// Receive end of ring
+const RXDESC_1_RER: u32 = 1 << 15;
+/// Second address chained
+const RXDESC_1_RCH: u32 = 1 << 14;
+/// Receive buffer 1 size
+const RXDESC_1_RBS_MASK: u32 = 0x1FFF;
+
+/// Transmit end of ring
+const TXDESC_0_TER: u32 = 1 << 21;
+/// Second address chained
+const TXDESC_0_TCH: u32 = 1 << 20;
+/// Error summary
+const TXDESC_0_ES: u32 = 1 << 15;
+/// The control bits of TDES0, which are not overwritten by the status.
+const TXDESC_0_CONTROL: u32 = 0x7FF0_0000;
+/// The errors that are included in the error summary: jabber timeout,
+/// frame flushed, payload checksum error, loss of carrier, no carrier,
+/// late collision, excessive collisions, excessive deferral, underflow
+/// and IP header error.
+const TXDESC_0_ES_ERRORS: u32 = (1 << 16)
+    | (1 << 14)
+    | (1 << 13)
+    | (1 << 12)
+    | (1 << 11)
+    | (1 << 10)
+    | (1 << 9)
+    | (1 << 8)
+    | (1 << 2)
+    | (1 << 1);
+/// Transmit buffer 1 size
+const TXDESC_1_TBS_MASK: u32 = 0x1FFF;
+
+/// `RPS`: Running, waiting for a frame
+const RPS_WAITING: u8 = 0b011;
+/// `RPS`: Suspended, receive descriptor unavailable
+const RPS_SUSPENDED: u8 = 0b100;
+/// `TPS`: Running, fetching a descriptor
+const TPS_FETCHING: u8 = 0b001;
+/// `TPS`: Suspended, transmit descriptor unavailable
+const TPS_SUSPENDED: u8 = 0b110;
+
+/// The outcome of delivering a frame to the simulated RX DMA.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Received {
+    /// The frame was written into the ring.
+    Complete,
+    /// Only some segments of the frame were written, and the DMA
+    /// still owns the descriptor that the frame continues in.
+    Partial,
+    /// The DMA did not own the first descriptor, and the
+    /// frame was counted as missed.
+    Missed,
+    /// The DMA did not own the next descriptor, so the frame was
+    /// closed early with a descriptor error.
+    Truncated,
+    /// The RX DMA was not started.
+    Stopped,
+}
+
+#[derive(Default)]
+struct Channel {
+    list: usize,
+    current: usize,
+    running: bool,
+    suspended: bool,
+    polls: usize,
+    /// The amount of bytes of the current frame that have been written.
+    offset: usize,
+}
+
+impl Channel {
+    fn start(&mut self, list: usize) {
+        self.list = list;
+        self.current = list;
+        self.running = true;
+        self.suspended = false;
+        self.offset = 0;
+    }
+
+    /// Extend the lower 32 bits of an address that was written
+    /// into a descriptor to a full pointer.
+    fn address(&self, low: u32) -> usize {
+        (self.list & !(u32::MAX as usize)) | low as usize
+    }
+}
+
+/// A simulated ethernet DMA.
+#[derive(Default)]
+pub struct SimDma {
+    rx: RefCell<Channel>,
+    tx: RefCell<Channel>,
+    missed_frames: Cell<u32>,
+    fatal_bus_error: Cell<bool>,
+    config: Cell<Option<EthernetConfig>>,
+    interrupt_enable: Cell<u32>,
+    resets: Cell<usize>,
+}
+
+fn read(desc: usize, n: usize) -> u32 {
+    unsafe { core::ptr::read_volatile((desc as *const u32).add(n)) }
+}
+
+fn write(desc: usize, n: usize, value: u32) {
+    unsafe { core::ptr::write_volatile((desc as *mut u32).add(n), value) }
+}
+
+impl SimDma {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The amount of RX poll demands.
+    pub fn rx_polls(&self) -> usize {
+        self.rx.borrow().polls
+    }
+
+    /// The amount of TX poll demands.
+    pub fn tx_polls(&self) -> usize {
+        self.tx.borrow().polls
+    }
+
+    /// Stop both DMA processes with a fatal bus error, like
+    /// the DMA does when a bus access fails.
+    pub fn fatal_bus_error(&self) {
+        self.rx.borrow_mut().running = false;
+        self.tx.borrow_mut().running = false;
+        self.fatal_bus_error.set(true);
+    }
+
+    /// Whether a fatal bus error is pending in the status register.
+    pub fn fatal_bus_error_pending(&self) -> bool {
+        self.fatal_bus_error.get()
+    }
+
+    /// The configuration that was written since the last reset.
+    pub fn config(&self) -> Option<EthernetConfig> {
+        self.config.get()
+    }
+
+    /// The amount of software resets.
+    pub fn resets(&self) -> usize {
+        self.resets.get()
+    }
+
+    /// Receive `frame` without errors.
+    pub fn receive(&self, frame: &[u8]) -> Received {
+        self.receive_with_status(frame, 0, 0)
+    }
+
+    /// Receive `frame`, and report the status bits `rdes0` and
+    /// the extended status `rdes4` in its last descriptor.
+    pub fn receive_with_status(&self, frame: &[u8], rdes0: u32, rdes4: u32) -> Received {
+        self.receive_segments(frame, rdes0, rdes4, usize::MAX)
+    }
+
+    /// Write at most `segments` more segments of `frame` into the ring.
+    ///
+    /// A frame that is [`Received::Partial`] is continued by
+    /// the next call, which must pass the same frame.
+    pub fn receive_segments(
+        &self,
+        frame: &[u8],
+        rdes0: u32,
+        #[allow(unused_variables)] rdes4: u32,
+        segments: usize,
+    ) -> Received {
+        let mut rx = self.rx.borrow_mut();
+        if !rx.running {
+            return Received::Stopped;
+        }
+
+        let mut written = 0;
+        loop {
+            let desc = rx.current;
+
+            if read(desc, 0) & DESC_0_OWN == 0 {
+                rx.suspended = true;
+                return if rx.offset == 0 {
+                    self.missed_frames.set(self.missed_frames.get() + 1);
+                    Received::Missed
+                } else {
+                    rx.offset = 0;
+                    Received::Truncated
+                };
+            }
+            rx.suspended = false;
+
+            let rdes1 = read(desc, 1);
+            let size = (rdes1 & RXDESC_1_RBS_MASK) as usize;
+            let buffer = rx.address(read(desc, 2)) as *mut u8;
+
+            let remaining = &frame[rx.offset..];
+            let len = remaining.len().min(size);
+            unsafe { core::ptr::copy_nonoverlapping(remaining.as_ptr(), buffer, len) };
+
+            let first = rx.offset == 0;
+            rx.offset += len;
+            let last = rx.offset == frame.len();
+
+            let next = if rdes1 & RXDESC_1_RER == RXDESC_1_RER {
+                rx.list
+            } else if rdes1 & RXDESC_1_RCH == RXDESC_1_RCH {
+                rx.address(read(desc, 3))
+            } else {
+                panic!("The simulated DMA only supports chained descriptors");
+            };
+
+            let mut status = if first { RXDESC_0_FS } else { 0 };
+
+            if last {
+                status |= RXDESC_0_LS | ((frame.len() as u32) << RXDESC_0_FL_SHIFT) | rdes0;
+                if rdes0 & RXDESC_0_ES_ERRORS != 0 {
+                    status |= RXDESC_0_ES;
+                }
+
+                #[cfg(not(feature = "stm32f107"))]
+                write(desc, 4, rdes4);
+            } else if read(next, 0) & DESC_0_OWN == 0 {
+                // The frame does not fit, and the next descriptor is not available.
+                write(desc, 0, status | RXDESC_0_DE | RXDESC_0_ES);
+                rx.current = next;
+                rx.offset = 0;
+                rx.suspended = true;
+                return Received::Truncated;
+            }
+
+            // Writing the status passes the descriptor back to the CPU.
+            write(desc, 0, status);
+            rx.current = next;
+            written += 1;
+
+            if last {
+                rx.offset = 0;
+                return Received::Complete;
+            } else if written == segments {
+                return Received::Partial;
+            }
+        }
+    }
+
+    /// Drop the rest of a frame that is [`Received::Partial`], as if
+    /// reception was interrupted. Its first segments are not closed.
+    pub fn abort_frame(&self) {
+        self.rx.borrow_mut().offset = 0;
+    }
+
+    /// Transmit the next packet without errors, and copy it into `buffer`.
+    ///
+    /// Returns the length of the packet, or `None` if the DMA
+    /// does not own the next descriptor.
+    pub fn transmit(&self, buffer: &mut [u8]) -> Option<usize> {
+        self.transmit_with_status(buffer, 0)
+    }
+
+    /// Transmit the next packet, and report the status bits `tdes0`.
+    pub fn transmit_with_status(&self, buffer: &mut [u8], tdes0: u32) -> Option<usize> {
+        let mut tx = self.tx.borrow_mut();
+        if !tx.running {
+            return None;
+        }
+
+        let desc = tx.current;
+        let control = read(desc, 0);
+
+        if control & DESC_0_OWN == 0 {
+            tx.suspended = true;
+            return None;
+        }
+        tx.suspended = false;
+
+        let len = (read(desc, 1) & TXDESC_1_TBS_MASK) as usize;
+        let packet = tx.address(read(desc, 2)) as *const u8;
+        let buffer = &mut buffer[..len];
+        unsafe { core::ptr::copy_nonoverlapping(packet, buffer.as_mut_ptr(), len) };
+
+        let mut status = (control & TXDESC_0_CONTROL) | tdes0;
+        if tdes0 & TXDESC_0_ES_ERRORS != 0 {
+            status |= TXDESC_0_ES;
+        }
+        write(desc, 0, status);
+
+        tx.current = if control & TXDESC_0_TER == TXDESC_0_TER {
+            tx.list
+        } else if control & TXDESC_0_TCH == TXDESC_0_TCH {
+            tx.address(read(desc, 3))
+        } else {
+            panic!("The simulated DMA only supports chained descriptors");
+        };
+
+        Some(len)
+    }
+}
+
+impl DmaRegisters for SimDma {
+    fn set_rx_descriptor_list(&self, address: *const RxDescriptor) {
+        self.rx.borrow_mut().list = address as usize;
+    }
+
+    fn start_rx(&self) {
+        let mut rx = self.rx.borrow_mut();
+        let list = rx.list;
+        rx.start(list);
+    }
+
+    fn demand_rx_poll(&self) {
+        let mut rx = self.rx.borrow_mut();
+        rx.polls += 1;
+        rx.suspended = read(rx.current, 0) & DESC_0_OWN == 0;
+    }
+
+    fn rx_process_state(&self) -> u8 {
+        let rx = self.rx.borrow();
+        match (rx.running, rx.suspended) {
+            (false, _) => 0,
+            (true, false) => RPS_WAITING,
+            (true, true) => RPS_SUSPENDED,
+        }
+    }
+
+    fn read_missed_frames(&self) -> u32 {
+        self.missed_frames.replace(0)
+    }
+
+    fn set_tx_descriptor_list(&self, address: *const TxDescriptor) {
+        self.tx.borrow_mut().list = address as usize;
+    }
+
+    fn start_tx(&self) {
+        let mut tx = self.tx.borrow_mut();
+        let list = tx.list;
+        tx.start(list);
+    }
+
+    fn demand_tx_poll(&self) {
+        let mut tx = self.tx.borrow_mut();
+        tx.polls += 1;
+        tx.suspended = read(tx.current, 0) & DESC_0_OWN == 0;
+    }
+
+    fn tx_process_state(&self) -> u8 {
+        let tx = self.tx.borrow();
+        match (tx.running, tx.suspended) {
+            (false, _) => 0,
+            (true, false) => TPS_FETCHING,
+            (true, true) => TPS_SUSPENDED,
+        }
+    }
+
+    fn stop_rx(&self) {
+        self.rx.borrow_mut().running = false;
+    }
+
+    fn stop_tx(&self) {
+        self.tx.borrow_mut().running = false;
+    }
+
+    fn flush_tx_fifo(&self) {}
+
+    fn tx_fifo_flushing(&self) -> bool {
+        false
+    }
+
+    fn software_reset(&self) {
+        *self.rx.borrow_mut() = Channel::default();
+        *self.tx.borrow_mut() = Channel::default();
+        self.missed_frames.set(0);
+        self.fatal_bus_error.set(false);
+        self.config.set(None);
+        self.interrupt_enable.set(0);
+        self.resets.set(self.resets.get() + 1);
+    }
+
+    fn resetting(&self) -> bool {
+        false
+    }
+
+    fn configure(&self, config: &EthernetConfig) {
+        self.config.set(Some(*config));
+    }
+
+    fn interrupt_enable(&self) -> u32 {
+        self.interrupt_enable.get()
+    }
+
+    fn set_interrupt_enable(&self, bits: u32) {
+        self.interrupt_enable.set(bits);
+    }
+
+    fn clear_status(&self) {
+        self.fatal_bus_error.set(false);
+    }
+}
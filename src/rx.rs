@@ -1,25 +1,184 @@
-use crate::stm32::ETHERNET_DMA;
-
 use core::{
     default::Default,
-    ops::{Deref, DerefMut},
     sync::atomic::{self, Ordering},
 };
 
 use crate::{
     desc::Descriptor,
-    ring::{RingDescriptor, RingEntry},
+    ring::{DmaRegisters, RingDescriptor, RingEntry, DEFAULT_BUFFER_SIZE},
 };
 
+#[cfg(feature = "ptp")]
+use crate::ptp::Timestamp;
+
 /// Errors that can occur during RX
 #[derive(Debug, PartialEq)]
 pub enum RxError {
     /// Receiving would block
     WouldBlock,
-    /// The received packet was truncated
+    /// The received packet was truncated, because it did not fit
+    /// in the ring or because some of its segments were lost
     Truncated,
-    /// An error occured with the DMA
-    DmaError,
+    /// An error occured with the DMA, or the received frame is erroneous
+    DmaError(RxErrors),
+}
+
+/// The status of an erroneous received frame, as reported by
+/// the DMA in its descriptor.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RxErrors {
+    /// The frame has an incorrect CRC.
+    pub crc_error: bool,
+    /// The PHY signaled a receive error while the frame was being received.
+    pub receive_error: bool,
+    /// The frame had a non-integer multiple of bytes (odd nibbles).
+    /// Only valid in MII mode.
+    pub dribble_bit: bool,
+    /// The receive FIFO overflowed while the frame was being received,
+    /// because the DMA could not keep up.
+    pub overflow: bool,
+    /// A late collision occured while the frame was being received
+    /// in half-duplex mode.
+    pub late_collision: bool,
+    /// The receive watchdog timer expired: the frame was
+    /// longer than 2048 bytes.
+    pub watchdog_timeout: bool,
+    /// The actual length of the frame does not match
+    /// the value of its length/type field.
+    pub length_error: bool,
+    /// The frame did not fit in the current descriptor's buffer,
+    /// and the DMA did not own the next descriptor.
+    pub descriptor_error: bool,
+    /// The frame failed the source address filter.
+    pub source_filter_fail: bool,
+    /// The frame failed the destination address filter.
+    pub destination_filter_fail: bool,
+    /// The checksum offload engine detected an error in the IP header.
+    ///
+    /// Frames that fail checksum verification are not dropped by the DMA,
+    /// but are reported with this error instead.
+    pub ip_header_error: bool,
+    /// The checksum offload engine detected an error in the
+    /// TCP/UDP/ICMP payload.
+    ///
+    /// Frames that fail checksum verification are not dropped by the DMA,
+    /// but are reported with this error instead.
+    pub payload_checksum_error: bool,
+}
+
+/// The amount of received frames that were dropped because of each
+/// type of error. A single frame can have multiple errors.
+///
+/// See [`RxErrors`] for a description of the errors.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RxErrorCounts {
+    /// Frames with [`RxErrors::crc_error`]
+    pub crc_error: u64,
+    /// Frames with [`RxErrors::receive_error`]
+    pub receive_error: u64,
+    /// Frames with [`RxErrors::dribble_bit`]
+    pub dribble_bit: u64,
+    /// Frames with [`RxErrors::overflow`]
+    pub overflow: u64,
+    /// Frames with [`RxErrors::late_collision`]
+    pub late_collision: u64,
+    /// Frames with [`RxErrors::watchdog_timeout`]
+    pub watchdog_timeout: u64,
+    /// Frames with [`RxErrors::length_error`]
+    pub length_error: u64,
+    /// Frames with [`RxErrors::descriptor_error`]
+    pub descriptor_error: u64,
+    /// Frames with [`RxErrors::source_filter_fail`]
+    pub source_filter_fail: u64,
+    /// Frames with [`RxErrors::destination_filter_fail`]
+    pub destination_filter_fail: u64,
+    /// Frames with [`RxErrors::ip_header_error`]
+    pub ip_header_error: u64,
+    /// Frames with [`RxErrors::payload_checksum_error`]
+    pub payload_checksum_error: u64,
+}
+
+impl RxErrorCounts {
+    fn count(&mut self, errors: &RxErrors) {
+        let mut count = |counter: &mut u64, error: bool| *counter += u64::from(error);
+
+        count(&mut self.crc_error, errors.crc_error);
+        count(&mut self.receive_error, errors.receive_error);
+        count(&mut self.dribble_bit, errors.dribble_bit);
+        count(&mut self.overflow, errors.overflow);
+        count(&mut self.late_collision, errors.late_collision);
+        count(&mut self.watchdog_timeout, errors.watchdog_timeout);
+        count(&mut self.length_error, errors.length_error);
+        count(&mut self.descriptor_error, errors.descriptor_error);
+        count(&mut self.source_filter_fail, errors.source_filter_fail);
+        count(
+            &mut self.destination_filter_fail,
+            errors.destination_filter_fail,
+        );
+        count(&mut self.ip_header_error, errors.ip_header_error);
+        count(
+            &mut self.payload_checksum_error,
+            errors.payload_checksum_error,
+        );
+    }
+}
+
+/// Statistics that the driver keeps about received frames.
+///
+/// See [`EthernetDMA::statistics`](crate::EthernetDMA::statistics).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RxStatistics {
+    /// Packets that were received successfully.
+    pub packets: u64,
+    /// Bytes that were received successfully.
+    pub bytes: u64,
+    /// Attempts to receive a packet while none was available.
+    pub would_block: u64,
+    /// Frames that were dropped because they were truncated.
+    pub truncated: u64,
+    /// Frames that were dropped by the VLAN-only filter.
+    pub filtered: u64,
+    /// Frames that were dropped because they were erroneous.
+    pub erroneous: u64,
+    /// The errors of the erroneous frames.
+    pub errors: RxErrorCounts,
+    /// Frames that the DMA missed because no descriptor was
+    /// available, as counted by `DMAMFBOCR`.
+    pub missed_frames: u64,
+    /// Frames that were lost because the RX FIFO overflowed,
+    /// as counted by `DMAMFBOCR`.
+    pub fifo_overflows: u64,
+    /// The highest amount of received frames' descriptors
+    /// that were waiting to be processed at the same time.
+    pub ring_high_water: usize,
+}
+
+/// The IEEE 802.1Q VLAN tag of a received frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VlanTag {
+    /// The priority code point (PCP).
+    pub pcp: u8,
+    /// The drop eligible indicator (DEI).
+    pub dei: bool,
+    /// The VLAN identifier (VID).
+    pub vid: u16,
+}
+
+impl VlanTag {
+    /// The EtherType that identifies an 802.1Q tagged frame.
+    const TPID: u16 = 0x8100;
+
+    fn from_tci(tci: u16) -> Self {
+        Self {
+            pcp: (tci >> 13) as u8,
+            dei: (tci & (1 << 12)) != 0,
+            vid: tci & 0xFFF,
+        }
+    }
 }
 
 /// Owned by DMA engine
@@ -30,17 +189,67 @@ const RXDESC_0_FS: u32 = 1 << 9;
 const RXDESC_0_LS: u32 = 1 << 8;
 /// Error summary
 const RXDESC_0_ES: u32 = 1 << 15;
+/// VLAN tag
+const RXDESC_0_VLAN: u32 = 1 << 10;
+/// Destination address filter fail
+const RXDESC_0_AFM: u32 = 1 << 30;
+/// Descriptor error
+const RXDESC_0_DE: u32 = 1 << 14;
+/// Source address filter fail
+const RXDESC_0_SAF: u32 = 1 << 13;
+/// Length error
+const RXDESC_0_LE: u32 = 1 << 12;
+/// Overflow error
+const RXDESC_0_OE: u32 = 1 << 11;
+/// Late collision
+const RXDESC_0_LCO: u32 = 1 << 6;
+/// Receive watchdog timeout
+const RXDESC_0_RWT: u32 = 1 << 4;
+/// Receive error
+const RXDESC_0_RE: u32 = 1 << 3;
+/// Dribble bit error
+const RXDESC_0_DBE: u32 = 1 << 2;
+/// CRC error
+const RXDESC_0_CE: u32 = 1 << 1;
+/// IP header checksum error
+#[cfg(feature = "stm32f107")]
+const RXDESC_0_IPHCE: u32 = 1 << 7;
+/// Frame type
+#[cfg(feature = "stm32f107")]
+const RXDESC_0_FT: u32 = 1 << 5;
+/// Payload checksum error
+#[cfg(feature = "stm32f107")]
+const RXDESC_0_PCE: u32 = 1 << 0;
+/// Extended status available
+#[cfg(not(feature = "stm32f107"))]
+const RXDESC_0_ESA: u32 = 1 << 0;
+/// Timestamp valid
+#[cfg(feature = "ptp")]
+const RXDESC_0_TSV: u32 = 1 << 7;
+/// Missed frames by the controller
+const DMAMFBOCR_MFC_MASK: u32 = 0xFFFF;
+/// Missed frames by the application
+const DMAMFBOCR_MFA_SHIFT: u32 = 17;
+const DMAMFBOCR_MFA_MASK: u32 = 0x7FF << DMAMFBOCR_MFA_SHIFT;
+
 /// Frame length
 const RXDESC_0_FL_MASK: u32 = 0x3FFF;
 const RXDESC_0_FL_SHIFT: usize = 16;
 
 const RXDESC_1_RBS_SHIFT: usize = 0;
-const RXDESC_1_RBS_MASK: u32 = 0x0fff << RXDESC_1_RBS_SHIFT;
+const RXDESC_1_RBS_MASK: u32 = 0x1fff << RXDESC_1_RBS_SHIFT;
 /// Second address chained
 const RXDESC_1_RCH: u32 = 1 << 14;
 /// End Of Ring
 const RXDESC_1_RER: u32 = 1 << 15;
 
+/// IP payload error
+#[cfg(not(feature = "stm32f107"))]
+const RXDESC_4_IPPE: u32 = 1 << 4;
+/// IP header error
+#[cfg(not(feature = "stm32f107"))]
+const RXDESC_4_IPHE: u32 = 1 << 3;
+
 #[repr(C)]
 #[derive(Clone)]
 /// An RX DMA Descriptor
@@ -88,6 +297,77 @@ impl RxDescriptor {
         (self.desc.read(0) & RXDESC_0_ES) == RXDESC_0_ES
     }
 
+    /// Whether the checksum offload engine detected an error in
+    /// the frame in this descriptor.
+    ///
+    /// Checksum errors are not always included in the error
+    /// summary, so they must be checked separately.
+    fn has_checksum_error(&self) -> bool {
+        let (ip_header_error, payload_checksum_error) = self.checksum_errors();
+        ip_header_error || payload_checksum_error
+    }
+
+    /// Decode the IP header and payload checksum status of
+    /// the frame in this descriptor.
+    fn checksum_errors(&self) -> (bool, bool) {
+        let rdes0 = self.desc.read(0);
+        let is_set = |bit: u32| (rdes0 & bit) == bit;
+
+        // The IP checksum status is reported in RDES0 by the normal
+        // descriptors of the f107, and in RDES4 by the enhanced descriptors.
+        //
+        // On the f107, the checksum status bits only have this meaning
+        // if the frame is an Ethernet-II (type) frame.
+        #[cfg(feature = "stm32f107")]
+        let errors = if is_set(RXDESC_0_FT) {
+            (is_set(RXDESC_0_IPHCE), is_set(RXDESC_0_PCE))
+        } else {
+            (false, false)
+        };
+
+        #[cfg(not(feature = "stm32f107"))]
+        let errors = if is_set(RXDESC_0_ESA) {
+            let rdes4 = self.desc.read(4);
+            (
+                (rdes4 & RXDESC_4_IPHE) == RXDESC_4_IPHE,
+                (rdes4 & RXDESC_4_IPPE) == RXDESC_4_IPPE,
+            )
+        } else {
+            (false, false)
+        };
+
+        errors
+    }
+
+    /// Decode the error status of the frame in this descriptor.
+    fn errors(&self) -> RxErrors {
+        let rdes0 = self.desc.read(0);
+        let is_set = |bit: u32| (rdes0 & bit) == bit;
+
+        let (ip_header_error, payload_checksum_error) = self.checksum_errors();
+
+        RxErrors {
+            crc_error: is_set(RXDESC_0_CE),
+            receive_error: is_set(RXDESC_0_RE),
+            dribble_bit: is_set(RXDESC_0_DBE),
+            overflow: is_set(RXDESC_0_OE),
+            late_collision: is_set(RXDESC_0_LCO),
+            watchdog_timeout: is_set(RXDESC_0_RWT),
+            length_error: is_set(RXDESC_0_LE),
+            descriptor_error: is_set(RXDESC_0_DE),
+            source_filter_fail: is_set(RXDESC_0_SAF),
+            destination_filter_fail: is_set(RXDESC_0_AFM),
+            ip_header_error,
+            payload_checksum_error,
+        }
+    }
+
+    /// The frame in this descriptor was marked as a VLAN
+    /// frame by the VLAN tag filter of the MAC.
+    fn is_vlan(&self) -> bool {
+        (self.desc.read(0) & RXDESC_0_VLAN) == RXDESC_0_VLAN
+    }
+
     /// Descriptor contains first buffer of frame
     fn is_first(&self) -> bool {
         (self.desc.read(0) & RXDESC_0_FS) == RXDESC_0_FS
@@ -123,10 +403,22 @@ impl RxDescriptor {
     fn get_frame_len(&self) -> usize {
         ((self.desc.read(0) >> RXDESC_0_FL_SHIFT) & RXDESC_0_FL_MASK) as usize
     }
+
+    /// The timestamp that was captured when the frame was received,
+    /// if any.
+    #[cfg(feature = "ptp")]
+    fn timestamp(&self) -> Option<Timestamp> {
+        if (self.desc.read(0) & RXDESC_0_TSV) == RXDESC_0_TSV {
+            // RDES7 contains the seconds, RDES6 the subseconds.
+            Some(Timestamp::from_parts(self.desc.read(7), self.desc.read(6)))
+        } else {
+            None
+        }
+    }
 }
 
 /// An RX DMA Ring Descriptor entry
-pub type RxRingEntry = RingEntry<RxDescriptor>;
+pub type RxRingEntry<const N: usize = DEFAULT_BUFFER_SIZE> = RingEntry<RxDescriptor, N>;
 
 impl RingDescriptor for RxDescriptor {
     fn setup(&mut self, buffer: *const u8, len: usize, next: Option<&Self>) {
@@ -147,84 +439,170 @@ impl RingDescriptor for RxDescriptor {
     }
 }
 
-impl RxRingEntry {
-    fn take_received(&mut self) -> Result<RxPacket, RxError> {
-        if self.desc().is_owned() {
-            Err(RxError::WouldBlock)
-        } else if self.desc().has_error() {
-            self.desc_mut().set_owned();
-            Err(RxError::DmaError)
-        } else if self.desc().is_first() && self.desc().is_last() {
-            let frame_len = self.desc().get_frame_len();
-
-            // "Subsequent reads and writes cannot be moved ahead of preceding reads."
-            atomic::compiler_fence(Ordering::Acquire);
-
-            // TODO: obtain ethernet frame type (RDESC_1_FT)
-            let pkt = RxPacket {
-                entry: self,
-                length: frame_len,
-            };
-            Ok(pkt)
-        } else {
-            self.desc_mut().set_owned();
-            Err(RxError::Truncated)
+/// A received packet.
+///
+/// A packet consists of one or more segments: one for every descriptor
+/// that the frame was received into. Frames that fit into the buffer of
+/// a single [`RxRingEntry`] always consist of a single segment.
+///
+/// A packet that consists of a single segment can be accessed in place with
+/// [`RxPacket::as_contiguous`]. Use [`RxPacket::segments`] or
+/// [`RxPacket::copy_into`] to access frames that span multiple segments.
+///
+/// The packet is passed back to the DMA engine when it is dropped.
+pub struct RxPacket<'a, const N: usize = DEFAULT_BUFFER_SIZE> {
+    entries: &'a mut [RxRingEntry<N>],
+    first: usize,
+    count: usize,
+    length: usize,
+}
+
+impl<'a, const N: usize> Drop for RxPacket<'a, N> {
+    fn drop(&mut self) {
+        let entries_len = self.entries.len();
+        for i in 0..self.count {
+            self.entries[(self.first + i) % entries_len]
+                .desc_mut()
+                .set_owned();
         }
     }
 }
 
-pub struct RxPacket<'a> {
-    entry: &'a mut RxRingEntry,
-    length: usize,
-}
+impl<'a, const N: usize> RxPacket<'a, N> {
+    /// Pass the packet back to the DMA engine.
+    pub fn free(self) {
+        drop(self)
+    }
 
-impl<'a> Deref for RxPacket<'a> {
-    type Target = [u8];
+    /// The length of the whole frame, across all segments.
+    pub fn frame_len(&self) -> usize {
+        self.length
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.entry.as_slice()[0..self.length]
+    /// The amount of segments (descriptors) that this packet consists of.
+    pub fn segment_count(&self) -> usize {
+        self.count
     }
-}
 
-impl<'a> DerefMut for RxPacket<'a> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.entry.as_mut_slice()[0..self.length]
+    /// The segments of this packet, in order.
+    pub fn segments(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let entries_len = self.entries.len();
+        let mut remaining = self.length;
+
+        (0..self.count).map(move |i| {
+            let buffer = self.entries[(self.first + i) % entries_len].as_slice();
+            let segment_len = remaining.min(buffer.len());
+            remaining -= segment_len;
+            &buffer[..segment_len]
+        })
     }
-}
 
-impl<'a> Drop for RxPacket<'a> {
-    fn drop(&mut self) {
-        self.entry.desc_mut().set_owned();
+    /// The whole frame, if this packet consists of a single segment.
+    ///
+    /// Returns `None` if the frame spans multiple segments.
+    pub fn as_contiguous(&self) -> Option<&[u8]> {
+        if self.count == 1 {
+            let buffer = self.entries[self.first].as_slice();
+            Some(&buffer[..self.length.min(buffer.len())])
+        } else {
+            None
+        }
     }
-}
 
-impl<'a> RxPacket<'a> {
-    // Pass back to DMA engine
-    pub fn free(self) {
-        drop(self)
+    /// The whole frame, mutably, if this packet consists of a single segment.
+    ///
+    /// Returns `None` if the frame spans multiple segments.
+    pub fn as_contiguous_mut(&mut self) -> Option<&mut [u8]> {
+        if self.count == 1 {
+            let buffer = self.entries[self.first].as_mut_slice();
+            let length = self.length.min(buffer.len());
+            Some(&mut buffer[..length])
+        } else {
+            None
+        }
+    }
+
+    /// Copy the whole frame into `buffer`.
+    ///
+    /// Returns the amount of bytes that were copied, which is less than
+    /// [`RxPacket::frame_len`] if `buffer` is too small to hold the frame.
+    pub fn copy_into(&self, buffer: &mut [u8]) -> usize {
+        let mut copied = 0;
+
+        for segment in self.segments() {
+            let len = segment.len().min(buffer.len() - copied);
+            buffer[copied..copied + len].copy_from_slice(&segment[..len]);
+            copied += len;
+
+            if copied == buffer.len() {
+                break;
+            }
+        }
+
+        copied
+    }
+
+    /// The IEEE 802.1Q VLAN tag of this packet, or `None`
+    /// if the frame is untagged.
+    pub fn vlan_tag(&self) -> Option<VlanTag> {
+        // The tag follows the destination and source addresses.
+        let header = self.get(12..16)?;
+
+        let tpid = u16::from_be_bytes([header[0], header[1]]);
+        if tpid != VlanTag::TPID {
+            return None;
+        }
+
+        let tci = u16::from_be_bytes([header[2], header[3]]);
+        Some(VlanTag::from_tci(tci))
+    }
+
+    /// The timestamp that the PTP peripheral captured when this
+    /// packet was received, if any.
+    #[cfg(feature = "ptp")]
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        let last = (self.first + self.count - 1) % self.entries.len();
+        self.entries[last].desc().timestamp()
     }
 }
 
 /// Rx DMA state
-pub struct RxRing<'a> {
-    entries: &'a mut [RxRingEntry],
+pub struct RxRing<'a, const N: usize> {
+    entries: &'a mut [RxRingEntry<N>],
     next_entry: usize,
+    checksum_offload: bool,
+    vlan_only: bool,
+    statistics: RxStatistics,
 }
 
-impl<'a> RxRing<'a> {
+impl<'a, const N: usize> RxRing<'a, N> {
     /// Allocate
-    pub fn new(entries: &'a mut [RxRingEntry]) -> Self {
+    pub fn new(entries: &'a mut [RxRingEntry<N>], checksum_offload: bool) -> Self {
         RxRing {
             entries,
             next_entry: 0,
+            checksum_offload,
+            vlan_only: false,
+            statistics: RxStatistics::default(),
         }
     }
 
+    /// Only receive frames that were marked as VLAN frames by the
+    /// MAC, and drop all other frames.
+    pub fn set_vlan_only(&mut self, vlan_only: bool) {
+        self.vlan_only = vlan_only;
+    }
+
+    /// Whether checksums of received packets are verified by the MAC.
+    pub fn checksum_offload(&self) -> bool {
+        self.checksum_offload
+    }
+
     /// Setup the DMA engine (**required**)
-    pub fn start(&mut self, eth_dma: &ETHERNET_DMA) {
+    pub fn start(&mut self, eth_dma: &impl DmaRegisters) {
         // Setup ring
         {
-            let mut previous: Option<&mut RxRingEntry> = None;
+            let mut previous: Option<&mut RxRingEntry<N>> = None;
             for entry in self.entries.iter_mut() {
                 if let Some(prev_entry) = &mut previous {
                     prev_entry.setup(Some(entry));
@@ -239,27 +617,25 @@ impl<'a> RxRing<'a> {
         let ring_ptr = self.entries[0].desc() as *const RxDescriptor;
 
         // Register RxDescriptor
-        eth_dma
-            .dmardlar
-            .write(|w| unsafe { w.srl().bits(ring_ptr as u32) });
+        eth_dma.set_rx_descriptor_list(ring_ptr);
 
         // We already have fences in `set_owned`, which is called in `setup`
 
         // Start receive
-        eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+        eth_dma.start_rx();
 
         self.demand_poll(eth_dma);
     }
 
     /// Demand that the DMA engine polls the current `RxDescriptor`
     /// (when in `RunningState::Stopped`.)
-    pub fn demand_poll(&self, eth_dma: &ETHERNET_DMA) {
-        eth_dma.dmarpdr.write(|w| unsafe { w.rpd().bits(1) });
+    pub fn demand_poll(&self, eth_dma: &impl DmaRegisters) {
+        eth_dma.demand_rx_poll();
     }
 
     /// Get current `RunningState`
-    pub fn running_state(&self, eth_dma: &ETHERNET_DMA) -> RunningState {
-        match eth_dma.dmasr.read().rps().bits() {
+    pub fn running_state(&self, eth_dma: &impl DmaRegisters) -> RunningState {
+        match eth_dma.rx_process_state() {
             //  Reset or Stop Receive Command issued
             0b000 => RunningState::Stopped,
             //  Fetching receive transfer descriptor
@@ -278,25 +654,194 @@ impl<'a> RxRing<'a> {
 
     /// Receive the next packet (if any is ready), or return `None`
     /// immediately.
-    pub fn recv_next(&mut self, eth_dma: &ETHERNET_DMA) -> Result<RxPacket, RxError> {
+    pub fn recv_next(&mut self, eth_dma: &impl DmaRegisters) -> Result<RxPacket<N>, RxError> {
         if !self.running_state(eth_dma).is_running() {
             self.demand_poll(eth_dma);
         }
 
+        self.update_high_water();
+
+        loop {
+            return match self.frame_at(self.next_entry) {
+                NextFrame::Pending => {
+                    self.statistics.would_block += 1;
+                    Err(RxError::WouldBlock)
+                }
+                NextFrame::Truncated(count) => {
+                    self.statistics.truncated += 1;
+                    self.discard(count);
+                    Err(RxError::Truncated)
+                }
+                NextFrame::Erroneous(errors, count) => {
+                    self.statistics.erroneous += 1;
+                    self.statistics.errors.count(&errors);
+                    self.discard(count);
+                    Err(RxError::DmaError(errors))
+                }
+                NextFrame::Filtered(count) => {
+                    self.statistics.filtered += 1;
+                    self.discard(count);
+                    continue;
+                }
+                NextFrame::Received { count, length } => {
+                    // "Subsequent reads and writes cannot be moved ahead of preceding reads."
+                    atomic::compiler_fence(Ordering::Acquire);
+
+                    self.statistics.packets += 1;
+                    self.statistics.bytes += length as u64;
+
+                    let first = self.next_entry;
+                    self.next_entry = (first + count) % self.entries.len();
+
+                    Ok(RxPacket {
+                        entries: &mut *self.entries,
+                        first,
+                        count,
+                        length,
+                    })
+                }
+            };
+        }
+    }
+
+    /// Count the descriptors that are waiting to be processed,
+    /// starting at `next_entry`.
+    fn update_high_water(&mut self) {
+        let entries_len = self.entries.len();
+
+        let ready = (0..entries_len)
+            .take_while(|i| {
+                let entry = (self.next_entry + i) % entries_len;
+                !self.entries[entry].desc().is_owned()
+            })
+            .count();
+
+        let high_water = &mut self.statistics.ring_high_water;
+        *high_water = (*high_water).max(ready);
+    }
+
+    /// The statistics about received frames.
+    ///
+    /// The missed frame counters of the DMA are cleared when they are read,
+    /// so they are accumulated into the statistics.
+    pub fn statistics(&mut self, eth_dma: &impl DmaRegisters) -> RxStatistics {
+        let dmamfbocr = eth_dma.read_missed_frames();
+
+        self.statistics.missed_frames += u64::from(dmamfbocr & DMAMFBOCR_MFC_MASK);
+        self.statistics.fifo_overflows +=
+            u64::from((dmamfbocr & DMAMFBOCR_MFA_MASK) >> DMAMFBOCR_MFA_SHIFT);
+
+        self.statistics
+    }
+
+    /// Reset the statistics about received frames.
+    pub fn reset_statistics(&mut self, eth_dma: &impl DmaRegisters) {
+        // Clear the missed frame counters by reading them.
+        let _ = eth_dma.read_missed_frames();
+        self.statistics = RxStatistics::default();
+    }
+
+    /// Whether [`RxRing::recv_next`] would return something
+    /// other than [`RxError::WouldBlock`].
+    pub fn frame_available(&self) -> bool {
         let entries_len = self.entries.len();
-        let result = self.entries[self.next_entry].take_received();
-        match result {
-            Err(RxError::WouldBlock) => {}
-            _ => {
-                self.next_entry += 1;
-                if self.next_entry >= entries_len {
-                    self.next_entry = 0;
+        let mut first = self.next_entry;
+        let mut skipped = 0;
+
+        // Frames that are dropped by `recv_next` do not count.
+        loop {
+            match self.frame_at(first) {
+                NextFrame::Pending => return false,
+                NextFrame::Filtered(count) => {
+                    skipped += count;
+                    if skipped >= entries_len {
+                        return false;
+                    }
+                    first = (first + count) % entries_len;
                 }
+                _ => return true,
             }
         }
+    }
+
+    /// Determine the state of the frame that starts at entry `first`.
+    fn frame_at(&self, first: usize) -> NextFrame {
+        let entries_len = self.entries.len();
+
+        // Find the descriptor that contains the last segment of the frame.
+        let mut count = 0;
+        let last = loop {
+            let desc = self.entries[(first + count) % entries_len].desc();
+
+            if desc.is_owned() {
+                return NextFrame::Pending;
+            }
+
+            // Every frame must start with a first segment, and may not
+            // contain the first segment of another frame.
+            if desc.is_first() != (count == 0) {
+                return NextFrame::Truncated(count.max(1));
+            }
+
+            count += 1;
 
-        result
+            // A descriptor error closes the frame early, without
+            // a last segment.
+            if !desc.is_last() && desc.has_error() {
+                return NextFrame::Erroneous(desc.errors(), count);
+            }
+
+            if desc.is_last() {
+                break desc;
+            }
+
+            // The frame does not fit in the ring.
+            if count == entries_len {
+                return NextFrame::Truncated(count);
+            }
+        };
+
+        // The status of the frame is only valid in the last descriptor.
+        if last.has_error() || last.has_checksum_error() {
+            NextFrame::Erroneous(last.errors(), count)
+        } else if self.vlan_only && !last.is_vlan() {
+            NextFrame::Filtered(count)
+        } else {
+            NextFrame::Received {
+                count,
+                length: last.get_frame_len(),
+            }
+        }
     }
+
+    /// Pass the next `count` descriptors back to the DMA engine
+    /// without receiving them.
+    fn discard(&mut self, count: usize) {
+        let entries_len = self.entries.len();
+        for i in 0..count {
+            self.entries[(self.next_entry + i) % entries_len]
+                .desc_mut()
+                .set_owned();
+        }
+        self.next_entry = (self.next_entry + count) % entries_len;
+    }
+}
+
+/// The state of a frame in an [`RxRing`].
+enum NextFrame {
+    /// The frame has not been (completely) received yet.
+    Pending,
+    /// The frame is incomplete, and its first `count` descriptors
+    /// must be discarded.
+    Truncated(usize),
+    /// The frame is erroneous, and its `count` descriptors
+    /// must be discarded.
+    Erroneous(RxErrors, usize),
+    /// The frame was dropped by the VLAN-only filter, and
+    /// its `count` descriptors must be discarded.
+    Filtered(usize),
+    /// The frame was received into `count` descriptors.
+    Received { count: usize, length: usize },
 }
 
 /// Running state of the `RxRing`
@@ -313,3 +858,253 @@ impl RunningState {
         *self == RunningState::Running
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::sim::{Received, SimDma};
+
+    const N: usize = 128;
+
+    fn frame<const L: usize>(seed: u8) -> [u8; L] {
+        let mut frame = [0; L];
+        for (i, byte) in frame.iter_mut().enumerate() {
+            *byte = seed.wrapping_add(i as u8);
+        }
+        frame
+    }
+
+    fn assert_received(ring: &mut RxRing<N>, dma: &SimDma, expected: &[u8]) {
+        let packet = ring.recv_next(dma).expect("a frame should be available");
+        assert_eq!(packet.frame_len(), expected.len());
+
+        let mut buffer = [0; 512];
+        let len = packet.copy_into(&mut buffer);
+        assert_eq!(&buffer[..len], expected);
+    }
+
+    #[test]
+    fn receive_single_segment() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        assert_eq!(ring.recv_next(&dma).err(), Some(RxError::WouldBlock));
+
+        let expected = frame::<60>(0);
+        assert_eq!(dma.receive(&expected), Received::Complete);
+        assert!(ring.frame_available());
+
+        let packet = ring.recv_next(&dma).unwrap();
+        assert_eq!(packet.segment_count(), 1);
+        assert_eq!(packet.as_contiguous(), Some(&expected[..]));
+        drop(packet);
+
+        assert!(!ring.frame_available());
+        assert_eq!(ring.recv_next(&dma).err(), Some(RxError::WouldBlock));
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut entries = [RxRingEntry::<N>::INIT; 3];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        for seed in 0..10 {
+            let expected = frame::<64>(seed);
+            assert_eq!(dma.receive(&expected), Received::Complete);
+            assert_received(&mut ring, &dma, &expected);
+        }
+
+        let statistics = ring.statistics(&dma);
+        assert_eq!(statistics.packets, 10);
+        assert_eq!(statistics.bytes, 640);
+        assert_eq!(statistics.missed_frames, 0);
+    }
+
+    #[test]
+    fn multi_segment_wraparound() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        // Every frame takes 3 descriptors, so the frames
+        // wrap around at different positions.
+        for seed in 0..5 {
+            let expected = frame::<300>(seed);
+            assert_eq!(dma.receive(&expected), Received::Complete);
+
+            let packet = ring.recv_next(&dma).unwrap();
+            assert_eq!(packet.segment_count(), 3);
+            assert_eq!(packet.as_contiguous(), None);
+            assert!(packet
+                .segments()
+                .zip([N, N, 300 - 2 * N])
+                .all(|(s, l)| s.len() == l));
+            drop(packet);
+        }
+    }
+
+    #[test]
+    fn frame_in_progress_is_pending() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        let expected = frame::<300>(7);
+
+        // The DMA has released the first descriptor, but
+        // still owns the rest of the frame.
+        assert_eq!(dma.receive_segments(&expected, 0, 0, 1), Received::Partial);
+        assert!(!ring.frame_available());
+        assert_eq!(ring.recv_next(&dma).err(), Some(RxError::WouldBlock));
+
+        assert_eq!(dma.receive_segments(&expected, 0, 0, 1), Received::Partial);
+        assert_eq!(ring.recv_next(&dma).err(), Some(RxError::WouldBlock));
+
+        assert_eq!(dma.receive(&expected), Received::Complete);
+        assert_received(&mut ring, &dma, &expected);
+    }
+
+    #[test]
+    fn full_ring_misses_frames() {
+        let mut entries = [RxRingEntry::<N>::INIT; 2];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        let first = frame::<60>(1);
+        let second = frame::<60>(2);
+        assert_eq!(dma.receive(&first), Received::Complete);
+        assert_eq!(dma.receive(&second), Received::Complete);
+        assert_eq!(dma.receive(&frame::<60>(3)), Received::Missed);
+
+        // The descriptor is not passed back while the packet is held.
+        let packet = ring.recv_next(&dma).unwrap();
+        assert_eq!(packet.as_contiguous(), Some(&first[..]));
+        assert_eq!(dma.receive(&frame::<60>(4)), Received::Missed);
+        drop(packet);
+
+        // The DMA is suspended, so receiving demands a poll.
+        let polls = dma.rx_polls();
+        assert_received(&mut ring, &dma, &second);
+        assert_eq!(dma.rx_polls(), polls + 1);
+
+        let third = frame::<60>(5);
+        assert_eq!(dma.receive(&third), Received::Complete);
+        assert_received(&mut ring, &dma, &third);
+
+        let statistics = ring.statistics(&dma);
+        assert_eq!(statistics.missed_frames, 2);
+        assert_eq!(statistics.ring_high_water, 2);
+
+        // The counter of the DMA is cleared when it is read.
+        assert_eq!(ring.statistics(&dma).missed_frames, 2);
+    }
+
+    #[test]
+    fn frame_larger_than_ring() {
+        let mut entries = [RxRingEntry::<N>::INIT; 2];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        // The DMA runs out of descriptors, and closes the frame
+        // with a descriptor error.
+        assert_eq!(dma.receive(&frame::<400>(0)), Received::Truncated);
+
+        match ring.recv_next(&dma) {
+            Err(RxError::DmaError(errors)) => assert!(errors.descriptor_error),
+            _ => panic!("expected a descriptor error"),
+        }
+
+        let expected = frame::<60>(1);
+        assert_eq!(dma.receive(&expected), Received::Complete);
+        assert_received(&mut ring, &dma, &expected);
+    }
+
+    #[test]
+    fn interrupted_frame_is_truncated() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        // The first segment is followed by the first segment of another frame.
+        assert_eq!(
+            dma.receive_segments(&frame::<300>(0), 0, 0, 1),
+            Received::Partial
+        );
+        dma.abort_frame();
+
+        let expected = frame::<60>(1);
+        assert_eq!(dma.receive(&expected), Received::Complete);
+
+        assert_eq!(ring.recv_next(&dma).err(), Some(RxError::Truncated));
+        assert_received(&mut ring, &dma, &expected);
+
+        let statistics = ring.statistics(&dma);
+        assert_eq!(statistics.truncated, 1);
+        assert_eq!(statistics.packets, 1);
+    }
+
+    #[test]
+    fn erroneous_frames() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, true);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        dma.receive_with_status(&frame::<300>(0), RXDESC_0_CE, 0);
+        match ring.recv_next(&dma) {
+            Err(RxError::DmaError(errors)) => assert_eq!(
+                errors,
+                RxErrors {
+                    crc_error: true,
+                    ..Default::default()
+                }
+            ),
+            _ => panic!("expected a CRC error"),
+        }
+
+        #[cfg(feature = "stm32f107")]
+        let (rdes0, rdes4) = (RXDESC_0_FT | RXDESC_0_PCE, 0);
+        #[cfg(not(feature = "stm32f107"))]
+        let (rdes0, rdes4) = (RXDESC_0_ESA, RXDESC_4_IPPE);
+
+        dma.receive_with_status(&frame::<60>(1), rdes0, rdes4);
+        match ring.recv_next(&dma) {
+            Err(RxError::DmaError(errors)) => assert!(errors.payload_checksum_error),
+            _ => panic!("expected a checksum error"),
+        }
+
+        let expected = frame::<60>(2);
+        dma.receive(&expected);
+        assert_received(&mut ring, &dma, &expected);
+
+        let statistics = ring.statistics(&dma);
+        assert_eq!(statistics.erroneous, 2);
+        assert_eq!(statistics.errors.crc_error, 1);
+        assert_eq!(statistics.errors.payload_checksum_error, 1);
+    }
+
+    #[test]
+    fn vlan_only() {
+        let mut entries = [RxRingEntry::<N>::INIT; 4];
+        let mut ring = RxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+        ring.set_vlan_only(true);
+
+        let expected = frame::<64>(1);
+        dma.receive(&frame::<60>(0));
+        dma.receive_with_status(&expected, RXDESC_0_VLAN, 0);
+
+        assert_received(&mut ring, &dma, &expected);
+        assert_eq!(ring.statistics(&dma).filtered, 1);
+    }
+}
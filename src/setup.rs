@@ -8,10 +8,13 @@
 use stm32f4xx_hal::{
     bb,
     gpio::{
-        gpioa::{PA1, PA7},
-        gpiob::{PB11, PB12, PB13},
-        gpioc::{PC4, PC5},
+        gpioa::{PA0, PA1, PA3, PA7},
+        gpiob::{PB0, PB1, PB10, PB11, PB12, PB13, PB8},
+        gpioc::{PC2, PC3, PC4, PC5},
+        gpioe::PE2,
         gpiog::{PG11, PG13, PG14},
+        gpioh::{PH2, PH3, PH6, PH7},
+        gpioi::PI10,
         Input,
         Speed::VeryHigh,
     },
@@ -23,24 +26,43 @@ use cortex_m::interrupt;
 #[cfg(feature = "stm32f7xx-hal")]
 use stm32f7xx_hal::{
     gpio::{
-        gpioa::{PA1, PA7},
-        gpiob::{PB11, PB12, PB13},
-        gpioc::{PC4, PC5},
+        gpioa::{PA0, PA1, PA3, PA7},
+        gpiob::{PB0, PB1, PB10, PB11, PB12, PB13, PB8},
+        gpioc::{PC2, PC3, PC4, PC5},
+        gpioe::PE2,
         gpiog::{PG11, PG13, PG14},
+        gpioh::{PH2, PH3, PH6, PH7},
+        gpioi::PI10,
         Input,
         Speed::VeryHigh,
     },
     pac::{RCC, SYSCFG},
 };
 
-// Enable syscfg and ethernet clocks. Reset the Ethernet MAC.
-pub(crate) fn setup() {
+/// The media independent interface that is used to
+/// connect the MAC to the PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaInterface {
+    /// Media Independent Interface
+    Mii,
+    /// Reduced Media Independent Interface
+    Rmii,
+}
+
+// Enable syscfg and ethernet clocks. Select the media interface
+// and reset the Ethernet MAC.
+pub(crate) fn setup(interface: MediaInterface) {
+    let rmii = interface == MediaInterface::Rmii;
+
     #[cfg(feature = "stm32f4xx-hal")]
     unsafe {
         const SYSCFG_BIT: u8 = 14;
         const ETH_MAC_BIT: u8 = 25;
         const ETH_TX_BIT: u8 = 26;
         const ETH_RX_BIT: u8 = 27;
+        #[cfg(feature = "ptp")]
+        const ETH_PTP_BIT: u8 = 28;
         const MII_RMII_BIT: u8 = 23;
 
         //NOTE(unsafe) This will only be used for atomic writes with no side-effects
@@ -56,12 +78,14 @@ pub(crate) fn setup() {
         }
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        bb::set(&syscfg.pmc, MII_RMII_BIT);
+        bb::write(&syscfg.pmc, MII_RMII_BIT, rmii);
 
         // enable ethernet clocks
         bb::set(&rcc.ahb1enr, ETH_MAC_BIT);
         bb::set(&rcc.ahb1enr, ETH_TX_BIT);
         bb::set(&rcc.ahb1enr, ETH_RX_BIT);
+        #[cfg(feature = "ptp")]
+        bb::set(&rcc.ahb1enr, ETH_PTP_BIT);
 
         // reset pulse
         bb::set(&rcc.ahb1rstr, ETH_MAC_BIT);
@@ -83,7 +107,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().set_bit());
+        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahb1enr.modify(|_, w| {
@@ -95,6 +119,9 @@ pub(crate) fn setup() {
                 .set_bit()
         });
 
+        #[cfg(feature = "ptp")]
+        rcc.ahb1enr.modify(|_, w| w.ethmacptpen().set_bit());
+
         //reset pulse
         rcc.ahb1rstr.modify(|_, w| w.ethmacrst().set_bit());
         rcc.ahb1rstr.modify(|_, w| w.ethmacrst().clear_bit());
@@ -115,7 +142,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        afio.mapr.modify(|_, w| w.mii_rmii_sel().set_bit());
+        afio.mapr.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahbenr.modify(|_, w| {
@@ -149,15 +176,16 @@ pub(crate) fn setup() {
 }
 
 macro_rules ! pin_trait {
-    ($([$name:ident, $doc:literal, $rm_name:literal]),*) => {
+    ($interface:literal, $([$name:ident, $doc:literal, $rm_name:literal]),*) => {
         $(
-        #[doc = concat!($doc, "\n# Safety\nOnly pins specified as `ETH_RMII_", $rm_name, "` in a part's Reference Manual\nmay implement this trait.")]
+        #[doc = concat!($doc, "\n# Safety\nOnly pins specified as `ETH_", $interface, "_", $rm_name, "` in a part's Reference Manual\nmay implement this trait.")]
         pub unsafe trait $name {}
         )*
     }
 }
 
 pin_trait!(
+    "RMII",
     [RmiiRefClk, "RMII Reference Clock", "REF_CLK"],
     [RmiiCrsDv, "RMII Rx Data Valid", "CRS_DV"],
     [RmiiTxEN, "RMII TX Enable", "TX_EN"],
@@ -167,12 +195,43 @@ pin_trait!(
     [RmiiRxD1, "RMII RX Data Pin 1", "RXD1"]
 );
 
+pin_trait!(
+    "MII",
+    [MiiTxClk, "MII TX Clock", "TX_CLK"],
+    [MiiRxClk, "MII RX Clock", "RX_CLK"],
+    [MiiTxEN, "MII TX Enable", "TX_EN"],
+    [MiiTxD0, "MII TX Data Pin 0", "TXD0"],
+    [MiiTxD1, "MII TX Data Pin 1", "TXD1"],
+    [MiiTxD2, "MII TX Data Pin 2", "TXD2"],
+    [MiiTxD3, "MII TX Data Pin 3", "TXD3"],
+    [MiiRxDv, "MII RX Data Valid", "RX_DV"],
+    [MiiRxD0, "MII RX Data Pin 0", "RXD0"],
+    [MiiRxD1, "MII RX Data Pin 1", "RXD1"],
+    [MiiRxD2, "MII RX Data Pin 2", "RXD2"],
+    [MiiRxD3, "MII RX Data Pin 3", "RXD3"],
+    [MiiRxEr, "MII RX Error", "RX_ER"],
+    [MiiCol, "MII Collision Detect", "COL"],
+    [MiiCrs, "MII Carrier Sense", "CRS"]
+);
+
 /// Trait needed to setup the pins for the Ethernet peripheral.
 pub trait AlternateVeryHighSpeed {
     /// Puts the pin in the Alternate Function 11 with Very High Speed.
     fn into_af11_very_high_speed(self);
 }
 
+/// A combination of pins that connects the ethernet peripheral
+/// to a PHY.
+///
+/// This trait is implemented for [`EthPins`] (RMII) and [`MiiPins`] (MII).
+pub trait InterfacePins {
+    /// The media independent interface that these pins implement.
+    const INTERFACE: MediaInterface;
+
+    /// Set up the pins for use by the ethernet peripheral.
+    fn setup_pins(self);
+}
+
 /// A struct that represents a combination of pins to be used
 /// as RMII pins for the ethernet peripheral(s)
 // NOTE(missing_docs): all fields of this struct are self-explanatory
@@ -216,23 +275,224 @@ where
     }
 }
 
+impl<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1> InterfacePins
+    for EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>
+where
+    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
+    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
+    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
+    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
+    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+{
+    const INTERFACE: MediaInterface = MediaInterface::Rmii;
+
+    fn setup_pins(self) {
+        EthPins::setup_pins(self)
+    }
+}
+
+/// A struct that represents a combination of pins to be used
+/// as MII pins for the ethernet peripheral(s)
+// NOTE(missing_docs): all fields of this struct are self-explanatory
+#[allow(missing_docs)]
+pub struct MiiPins<
+    TXCLK,
+    RXCLK,
+    TXEN,
+    TXD0,
+    TXD1,
+    TXD2,
+    TXD3,
+    RXDV,
+    RXD0,
+    RXD1,
+    RXD2,
+    RXD3,
+    RXER,
+    COL,
+    CRS,
+> {
+    pub tx_clk: TXCLK,
+    pub rx_clk: RXCLK,
+    pub tx_en: TXEN,
+    pub tx_d0: TXD0,
+    pub tx_d1: TXD1,
+    pub tx_d2: TXD2,
+    pub tx_d3: TXD3,
+    pub rx_dv: RXDV,
+    pub rx_d0: RXD0,
+    pub rx_d1: RXD1,
+    pub rx_d2: RXD2,
+    pub rx_d3: RXD3,
+    pub rx_er: RXER,
+    pub col: COL,
+    pub crs: CRS,
+}
+
+impl<TXCLK, RXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXDV, RXD0, RXD1, RXD2, RXD3, RXER, COL, CRS>
+    MiiPins<
+        TXCLK,
+        RXCLK,
+        TXEN,
+        TXD0,
+        TXD1,
+        TXD2,
+        TXD3,
+        RXDV,
+        RXD0,
+        RXD1,
+        RXD2,
+        RXD3,
+        RXER,
+        COL,
+        CRS,
+    >
+where
+    TXCLK: MiiTxClk + AlternateVeryHighSpeed,
+    RXCLK: MiiRxClk + AlternateVeryHighSpeed,
+    TXEN: MiiTxEN + AlternateVeryHighSpeed,
+    TXD0: MiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: MiiTxD1 + AlternateVeryHighSpeed,
+    TXD2: MiiTxD2 + AlternateVeryHighSpeed,
+    TXD3: MiiTxD3 + AlternateVeryHighSpeed,
+    RXDV: MiiRxDv + AlternateVeryHighSpeed,
+    RXD0: MiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: MiiRxD1 + AlternateVeryHighSpeed,
+    RXD2: MiiRxD2 + AlternateVeryHighSpeed,
+    RXD3: MiiRxD3 + AlternateVeryHighSpeed,
+    RXER: MiiRxEr + AlternateVeryHighSpeed,
+    COL: MiiCol + AlternateVeryHighSpeed,
+    CRS: MiiCrs + AlternateVeryHighSpeed,
+{
+    /// Pin setup.
+    ///
+    /// Set MII pins to
+    /// * Alternate function 11
+    /// * High-speed
+    ///
+    /// This function consumes the pins so that you cannot use them
+    /// anywhere else by accident.
+    pub fn setup_pins(self) {
+        self.tx_clk.into_af11_very_high_speed();
+        self.rx_clk.into_af11_very_high_speed();
+        self.tx_en.into_af11_very_high_speed();
+        self.tx_d0.into_af11_very_high_speed();
+        self.tx_d1.into_af11_very_high_speed();
+        self.tx_d2.into_af11_very_high_speed();
+        self.tx_d3.into_af11_very_high_speed();
+        self.rx_dv.into_af11_very_high_speed();
+        self.rx_d0.into_af11_very_high_speed();
+        self.rx_d1.into_af11_very_high_speed();
+        self.rx_d2.into_af11_very_high_speed();
+        self.rx_d3.into_af11_very_high_speed();
+        self.rx_er.into_af11_very_high_speed();
+        self.col.into_af11_very_high_speed();
+        self.crs.into_af11_very_high_speed();
+    }
+}
+
+impl<TXCLK, RXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXDV, RXD0, RXD1, RXD2, RXD3, RXER, COL, CRS>
+    InterfacePins
+    for MiiPins<
+        TXCLK,
+        RXCLK,
+        TXEN,
+        TXD0,
+        TXD1,
+        TXD2,
+        TXD3,
+        RXDV,
+        RXD0,
+        RXD1,
+        RXD2,
+        RXD3,
+        RXER,
+        COL,
+        CRS,
+    >
+where
+    TXCLK: MiiTxClk + AlternateVeryHighSpeed,
+    RXCLK: MiiRxClk + AlternateVeryHighSpeed,
+    TXEN: MiiTxEN + AlternateVeryHighSpeed,
+    TXD0: MiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: MiiTxD1 + AlternateVeryHighSpeed,
+    TXD2: MiiTxD2 + AlternateVeryHighSpeed,
+    TXD3: MiiTxD3 + AlternateVeryHighSpeed,
+    RXDV: MiiRxDv + AlternateVeryHighSpeed,
+    RXD0: MiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: MiiRxD1 + AlternateVeryHighSpeed,
+    RXD2: MiiRxD2 + AlternateVeryHighSpeed,
+    RXD3: MiiRxD3 + AlternateVeryHighSpeed,
+    RXER: MiiRxEr + AlternateVeryHighSpeed,
+    COL: MiiCol + AlternateVeryHighSpeed,
+    CRS: MiiCrs + AlternateVeryHighSpeed,
+{
+    const INTERFACE: MediaInterface = MediaInterface::Mii;
+
+    fn setup_pins(self) {
+        MiiPins::setup_pins(self)
+    }
+}
+
 #[allow(unused_macros)]
 macro_rules! impl_pins {
     ( $($traity:ident: [$($pin:ty,)+],)+ ) => {
         $(
             $(
                 unsafe impl $traity for $pin {}
+            )+
+        )+
+    };
+}
 
-                impl AlternateVeryHighSpeed for $pin {
-                    fn into_af11_very_high_speed(self) {
-                        self.into_alternate::<11>().set_speed(VeryHigh);
-                    }
+// Some pins can be used for both RMII and MII, so `AlternateVeryHighSpeed`
+// is implemented separately from the pin traits.
+#[allow(unused_macros)]
+macro_rules! impl_af11 {
+    ( $($pin:ty,)+ ) => {
+        $(
+            impl AlternateVeryHighSpeed for $pin {
+                fn into_af11_very_high_speed(self) {
+                    self.into_alternate::<11>().set_speed(VeryHigh);
                 }
-            )+
+            }
         )+
     };
 }
 
+#[cfg(all(
+    feature = "device-selected",
+    any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal")
+))]
+impl_af11!(
+    PA0<Input>,
+    PA1<Input>,
+    PA3<Input>,
+    PA7<Input>,
+    PB0<Input>,
+    PB1<Input>,
+    PB8<Input>,
+    PB10<Input>,
+    PB11<Input>,
+    PB12<Input>,
+    PB13<Input>,
+    PC2<Input>,
+    PC3<Input>,
+    PC4<Input>,
+    PC5<Input>,
+    PE2<Input>,
+    PG11<Input>,
+    PG13<Input>,
+    PG14<Input>,
+    PH2<Input>,
+    PH3<Input>,
+    PH6<Input>,
+    PH7<Input>,
+    PI10<Input>,
+);
+
 #[cfg(all(
     feature = "device-selected",
     any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal")
@@ -262,6 +522,60 @@ impl_pins!(
     RmiiRxD1: [
         PC5<Input>,
     ],
+    MiiTxClk: [
+        PC3<Input>,
+    ],
+    MiiRxClk: [
+        PA1<Input>,
+    ],
+    MiiTxEN: [
+        PB11<Input>,
+        PG11<Input>,
+    ],
+    MiiTxD0: [
+        PB12<Input>,
+        PG13<Input>,
+    ],
+    MiiTxD1: [
+        PB13<Input>,
+        PG14<Input>,
+    ],
+    MiiTxD2: [
+        PC2<Input>,
+    ],
+    MiiTxD3: [
+        PB8<Input>,
+        PE2<Input>,
+    ],
+    MiiRxDv: [
+        PA7<Input>,
+    ],
+    MiiRxD0: [
+        PC4<Input>,
+    ],
+    MiiRxD1: [
+        PC5<Input>,
+    ],
+    MiiRxD2: [
+        PB0<Input>,
+        PH6<Input>,
+    ],
+    MiiRxD3: [
+        PB1<Input>,
+        PH7<Input>,
+    ],
+    MiiRxEr: [
+        PB10<Input>,
+        PI10<Input>,
+    ],
+    MiiCol: [
+        PA3<Input>,
+        PH3<Input>,
+    ],
+    MiiCrs: [
+        PA0<Input>,
+        PH2<Input>,
+    ],
 );
 
 #[cfg(feature = "stm32f1xx-hal")]
@@ -273,48 +587,94 @@ mod stm32f1 {
     };
 
     // STM32F1xx's require access to the CRL/CRH registers to change pin mode. As a result, we
-    // require that pins are already in the necessary mode before constructing `EthPins` as it
-    // would be inconvenient to pass CRL and CRH through to the `AlternateVeryHighSpeed` callsite.
+    // require that pins are already in the necessary mode before constructing `EthPins` or
+    // `MiiPins` as it would be inconvenient to pass CRL and CRH through to the
+    // `AlternateVeryHighSpeed` callsite.
+
+    macro_rules! impl_af11 {
+        ($(($PIN:ty, $is_input:literal)),*) => {
+            $(
+                impl AlternateVeryHighSpeed for $PIN {
+                    fn into_af11_very_high_speed(self) {
+                        // Within this critical section, modifying the `CRL` register can
+                        // only be unsound if this critical section preempts other code
+                        // that is modifying the same register
+                        cortex_m::interrupt::free(|_| {
+                            // SAFETY: this is sound as long as the API of the HAL and structure of the CRL
+                            // struct does not change. In case the size of the `CRL` struct is changed, compilation
+                            // will fail as `mem::transmute` can only convert between types of the same size.
+                            //
+                            // This guards us from unsound behaviour introduced by point releases of the f1 hal
+                            let cr: &mut _ = &mut unsafe { core::mem::transmute(()) };
+                            // The speed can only be changed on output pins
+                            let mut pin = self.into_alternate_push_pull(cr);
+                            pin.set_speed(cr, IOPinSpeed::Mhz50);
+
+                            if $is_input {
+                                pin.into_floating_input(cr);
+                            }
+                        });
+                    }
+                }
+            )*
+        };
+    }
 
     macro_rules! impl_pins {
-        ($($type:ident: [$(($PIN:ty, $is_input:literal)),+]),*) => {
+        ($($type:ident: [$($PIN:ty),+]),*) => {
             $(
                 $(
                     unsafe impl $type for $PIN {}
-                    impl AlternateVeryHighSpeed for $PIN {
-                        fn into_af11_very_high_speed(self) {
-                            // Within this critical section, modifying the `CRL` register can
-                            // only be unsound if this critical section preempts other code
-                            // that is modifying the same register
-                            cortex_m::interrupt::free(|_| {
-                                // SAFETY: this is sound as long as the API of the HAL and structure of the CRL
-                                // struct does not change. In case the size of the `CRL` struct is changed, compilation
-                                // will fail as `mem::transmute` can only convert between types of the same size.
-                                //
-                                // This guards us from unsound behaviour introduced by point releases of the f1 hal
-                                let cr: &mut _ = &mut unsafe { core::mem::transmute(()) };
-                                // The speed can only be changed on output pins
-                                let mut pin = self.into_alternate_push_pull(cr);
-                                pin.set_speed(cr, IOPinSpeed::Mhz50);
-
-                                if $is_input {
-                                    pin.into_floating_input(cr);
-                                }
-                            });
-                        }
-                    }
                 )+
             )*
         };
     }
 
+    impl_af11!(
+        (PA0<Input<Floating>>, true),
+        (PA1<Input<Floating>>, true),
+        (PA3<Input<Floating>>, true),
+        (PA7<Input<Floating>>, true),
+        (PB0<Input<Floating>>, true),
+        (PB1<Input<Floating>>, true),
+        (PB8<Alternate<PushPull>>, false),
+        (PB10<Input<Floating>>, true),
+        (PB11<Alternate<PushPull>>, false),
+        (PB12<Alternate<PushPull>>, false),
+        (PB13<Alternate<PushPull>>, false),
+        (PC2<Alternate<PushPull>>, false),
+        (PC3<Input<Floating>>, true),
+        (PC4<Input<Floating>>, true),
+        (PC5<Input<Floating>>, true),
+        (PD8<Input<Floating>>, true),
+        (PD9<Input<Floating>>, true),
+        (PD10<Input<Floating>>, true),
+        (PD11<Input<Floating>>, true),
+        (PD12<Input<Floating>>, true)
+    );
+
     impl_pins!(
-        RmiiRefClk: [(PA1<Input<Floating>>, true)],
-        RmiiCrsDv: [(PA7<Input<Floating>>, true), (PD8<Input<Floating>>, true)],
-        RmiiTxEN: [(PB11<Alternate<PushPull>>, false)],
-        RmiiTxD0: [(PB12<Alternate<PushPull>>, false)],
-        RmiiTxD1: [(PB13<Alternate<PushPull>>, false)],
-        RmiiRxD0: [(PC4<Input<Floating>>, true), (PD9<Input<Floating>>, true)],
-        RmiiRxD1: [(PC5<Input<Floating>>, true), (PD10<Input<Floating>>, true)]
+        RmiiRefClk: [PA1<Input<Floating>>],
+        RmiiCrsDv: [PA7<Input<Floating>>, PD8<Input<Floating>>],
+        RmiiTxEN: [PB11<Alternate<PushPull>>],
+        RmiiTxD0: [PB12<Alternate<PushPull>>],
+        RmiiTxD1: [PB13<Alternate<PushPull>>],
+        RmiiRxD0: [PC4<Input<Floating>>, PD9<Input<Floating>>],
+        RmiiRxD1: [PC5<Input<Floating>>, PD10<Input<Floating>>],
+        MiiTxClk: [PC3<Input<Floating>>],
+        MiiRxClk: [PA1<Input<Floating>>],
+        MiiTxEN: [PB11<Alternate<PushPull>>],
+        MiiTxD0: [PB12<Alternate<PushPull>>],
+        MiiTxD1: [PB13<Alternate<PushPull>>],
+        MiiTxD2: [PC2<Alternate<PushPull>>],
+        MiiTxD3: [PB8<Alternate<PushPull>>],
+        MiiRxDv: [PA7<Input<Floating>>, PD8<Input<Floating>>],
+        MiiRxD0: [PC4<Input<Floating>>, PD9<Input<Floating>>],
+        MiiRxD1: [PC5<Input<Floating>>, PD10<Input<Floating>>],
+        MiiRxD2: [PB0<Input<Floating>>, PD11<Input<Floating>>],
+        MiiRxD3: [PB1<Input<Floating>>, PD12<Input<Floating>>],
+        MiiRxEr: [PB10<Input<Floating>>],
+        MiiCol: [PA3<Input<Floating>>],
+        MiiCrs: [PA0<Input<Floating>>]
     );
 }
@@ -1,81 +1,118 @@
-use crate::{rx::RxPacket, tx::TxError, EthernetDMA};
-use core::intrinsics::transmute;
-use smoltcp::phy::{ChecksumCapabilities, Device, DeviceCapabilities, RxToken, TxToken};
-use smoltcp::time::Instant;
-use smoltcp::Error;
+use crate::{
+    dma::TxHalf,
+    rx::RxPacket,
+    smoltcp::phy::{Checksum, Device, DeviceCapabilities, RxToken, TxToken},
+    smoltcp::time::Instant,
+    EthernetDMA,
+};
 
-/// Use this Ethernet driver with [smoltcp](https://github.com/m-labs/smoltcp)
-impl<'a, 'rx, 'tx, 'b> Device<'a> for &'b mut EthernetDMA<'rx, 'tx> {
-    type RxToken = EthRxToken<'a>;
-    type TxToken = EthTxToken<'a>;
+/// Use this Ethernet driver with [smoltcp](https://github.com/smoltcp-rs/smoltcp)
+impl<'rx, 'tx, const N: usize> Device for EthernetDMA<'rx, 'tx, N> {
+    type RxToken<'a>
+        = EthRxToken<'a, N>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = EthTxToken<'a, 'tx, N>
+    where
+        Self: 'a;
 
     fn capabilities(&self) -> DeviceCapabilities {
         let mut caps = DeviceCapabilities::default();
-        caps.max_transmission_unit = super::MTU;
+        // Frames that are sent must fit in a single TX buffer
+        caps.max_transmission_unit = N.min(super::MTU);
         caps.max_burst_size = Some(1);
-        caps.checksum = ChecksumCapabilities::ignored();
+
+        // The MAC verifies and inserts the IPv4 header checksum and the
+        // TCP/UDP/ICMP payload checksums, so smoltcp only has to handle
+        // the directions that are not offloaded.
+        let checksum = match (self.rx_checksum_offload(), self.tx_checksum_insertion()) {
+            (true, true) => Checksum::None,
+            (true, false) => Checksum::Tx,
+            (false, true) => Checksum::Rx,
+            (false, false) => Checksum::Both,
+        };
+
+        caps.checksum.ipv4 = checksum;
+        caps.checksum.udp = checksum;
+        caps.checksum.tcp = checksum;
+        caps.checksum.icmpv4 = checksum;
         caps
     }
 
-    fn receive(&mut self) -> Option<(Self::RxToken, Self::TxToken)> {
-        let self_ = unsafe {
-            // HACK: eliminate lifetimes
-            transmute::<&mut EthernetDMA<'rx, 'tx>, &mut EthernetDMA<'a, 'a>>(*self)
-        };
-        let eth = self_ as *mut EthernetDMA<'a, 'a>;
-        match self_.recv_next() {
-            Ok(packet) => {
-                let rx = EthRxToken { packet };
-                let tx = EthTxToken { eth };
-                Some((rx, tx))
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (rx, tx) = self.halves_mut();
+
+        // Only receive a frame if the reply to it can be sent.
+        if !tx.next_entry_available() {
+            return None;
+        }
+
+        match rx.recv_next() {
+            // Frames that span multiple descriptors are copied into a buffer
+            // of `MTU` bytes before they are passed on, so larger frames
+            // are dropped.
+            Ok(packet) if packet.segment_count() == 1 || packet.frame_len() <= super::MTU => {
+                Some((EthRxToken { packet }, EthTxToken { tx }))
             }
-            Err(_) => None,
+            _ => None,
         }
     }
 
-    fn transmit(&mut self) -> Option<Self::TxToken> {
-        let eth = unsafe {
-            transmute::<&mut EthernetDMA<'rx, 'tx>, &mut EthernetDMA<'a, 'a>>(*self)
-                as *mut EthernetDMA<'a, 'a>
-        };
-        Some(EthTxToken { eth })
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        let (_, tx) = self.halves_mut();
+
+        if tx.next_entry_available() {
+            Some(EthTxToken { tx })
+        } else {
+            None
+        }
     }
 }
 
 /// An Ethernet RX token that can be consumed in order to receive
 /// an ethernet packet.
-pub struct EthRxToken<'a> {
-    packet: RxPacket<'a>,
+pub struct EthRxToken<'a, const N: usize> {
+    packet: RxPacket<'a, N>,
 }
 
-impl<'a> RxToken for EthRxToken<'a> {
-    fn consume<R, F>(mut self, _timestamp: Instant, f: F) -> Result<R, Error>
+impl<'a, const N: usize> RxToken for EthRxToken<'a, N> {
+    fn consume<R, F>(mut self, f: F) -> R
     where
-        F: FnOnce(&mut [u8]) -> Result<R, Error>,
+        F: FnOnce(&mut [u8]) -> R,
     {
-        let result = f(&mut self.packet);
+        let result = if let Some(frame) = self.packet.as_contiguous_mut() {
+            f(frame)
+        } else {
+            // The frame spans multiple descriptors, so it
+            // has to be copied into a contiguous buffer.
+            let mut buffer = [0u8; super::MTU];
+            let len = self.packet.copy_into(&mut buffer);
+            f(&mut buffer[..len])
+        };
         self.packet.free();
         result
     }
 }
 
-/// Just a reference to [`Eth`](../struct.EthernetDMA.html) for sending a
-/// packet later with [`consume()`](#method.consume).
-pub struct EthTxToken<'a> {
-    eth: *mut EthernetDMA<'a, 'a>,
+/// A reference to the [`TxHalf`] of an [`EthernetDMA`] for sending
+/// a packet later with [`consume()`](#method.consume).
+pub struct EthTxToken<'a, 'tx, const N: usize> {
+    tx: &'a mut TxHalf<'tx, N>,
 }
 
-impl<'a> TxToken for EthTxToken<'a> {
-    /// Allocate a [`Buffer`](../struct.Buffer.html), yield with
-    /// `f(buffer)`, and send it as an Ethernet packet.
-    fn consume<R, F>(self, _timestamp: Instant, len: usize, f: F) -> Result<R, Error>
+impl<'a, 'tx, const N: usize> TxToken for EthTxToken<'a, 'tx, N> {
+    /// Yield the next TX buffer with `f(buffer)`, and send it as
+    /// an Ethernet packet.
+    fn consume<R, F>(self, len: usize, f: F) -> R
     where
-        F: FnOnce(&mut [u8]) -> Result<R, Error>,
+        F: FnOnce(&mut [u8]) -> R,
     {
-        let eth = unsafe { &mut *self.eth };
-        match eth.send(len, f) {
-            Err(TxError::WouldBlock) => Err(Error::Exhausted),
-            Ok(r) => r,
-        }
+        // The token is only handed out if an entry is
+        // available, so sending can not fail.
+        self.tx
+            .send(len, f)
+            .ok()
+            .expect("TX entry is not available")
     }
 }
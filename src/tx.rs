@@ -1,5 +1,3 @@
-use crate::stm32::ETHERNET_DMA;
-
 use core::{
     ops::{Deref, DerefMut},
     sync::atomic::{self, Ordering},
@@ -7,9 +5,29 @@ use core::{
 
 use crate::{
     desc::Descriptor,
-    ring::{RingDescriptor, RingEntry},
+    ring::{DmaRegisters, RingDescriptor, RingEntry, DEFAULT_BUFFER_SIZE},
 };
 
+#[cfg(feature = "ptp")]
+use crate::ptp::Timestamp;
+
+/// Statistics that the driver keeps about transmitted packets.
+///
+/// See [`EthernetDMA::statistics`](crate::EthernetDMA::statistics).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxStatistics {
+    /// Packets that were handed to the DMA for transmission.
+    pub packets: u64,
+    /// Bytes that were handed to the DMA for transmission.
+    pub bytes: u64,
+    /// Attempts to send a packet while the ring was full.
+    pub would_block: u64,
+    /// The highest amount of descriptors that were waiting
+    /// to be transmitted at the same time.
+    pub ring_high_water: usize,
+}
+
 /// Owned by DMA engine
 const TXDESC_0_OWN: u32 = 1 << 31;
 /// Interrupt on completion
@@ -18,6 +36,9 @@ const TXDESC_0_IC: u32 = 1 << 30;
 const TXDESC_0_FS: u32 = 1 << 28;
 /// Last segment of frame
 const TXDESC_0_LS: u32 = 1 << 29;
+/// Transmit timestamp enable
+#[cfg(feature = "ptp")]
+const TXDESC_0_TTSE: u32 = 1 << 25;
 /// Checksum insertion control
 const TXDESC_0_CIC0: u32 = 1 << 23;
 const TXDESC_0_CIC1: u32 = 1 << 22;
@@ -25,11 +46,34 @@ const TXDESC_0_CIC1: u32 = 1 << 22;
 const TXDESC_0_TER: u32 = 1 << 21;
 /// Second address chained
 const TXDESC_0_TCH: u32 = 1 << 20;
+/// Transmit timestamp status
+#[cfg(feature = "ptp")]
+const TXDESC_0_TTSS: u32 = 1 << 17;
 /// Error status
 const TXDESC_0_ES: u32 = 1 << 15;
+/// Jabber timeout
+const TXDESC_0_JT: u32 = 1 << 14;
+/// Frame flushed
+const TXDESC_0_FF: u32 = 1 << 13;
+/// IP payload error
+const TXDESC_0_IPE: u32 = 1 << 12;
+/// Loss of carrier
+const TXDESC_0_LCA: u32 = 1 << 11;
+/// No carrier
+const TXDESC_0_NC: u32 = 1 << 10;
+/// Late collision
+const TXDESC_0_LCO: u32 = 1 << 9;
+/// Excessive collision
+const TXDESC_0_EC: u32 = 1 << 8;
+/// Excessive deferral
+const TXDESC_0_ED: u32 = 1 << 2;
+/// Underflow error
+const TXDESC_0_UF: u32 = 1 << 1;
+/// IP header error
+const TXDESC_0_IHE: u32 = 1 << 16;
 
 const TXDESC_1_TBS_SHIFT: usize = 0;
-const TXDESC_1_TBS_MASK: u32 = 0x0fff << TXDESC_1_TBS_SHIFT;
+const TXDESC_1_TBS_MASK: u32 = 0x1fff << TXDESC_1_TBS_SHIFT;
 
 /// Errors that can occur during Ethernet TX
 #[derive(Debug, PartialEq)]
@@ -38,11 +82,88 @@ pub enum TxError {
     WouldBlock,
 }
 
+/// The status of a packet that was sent with an ID.
+///
+/// See [`EthernetDMA::tx_status`](crate::EthernetDMA::tx_status).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// The packet has not been transmitted yet.
+    Pending,
+    /// The packet was transmitted successfully.
+    Transmitted,
+    /// Transmission of the packet failed.
+    Failed(TxErrors),
+}
+
+/// The errors that occured while transmitting a packet, as
+/// reported by the DMA in the status of its descriptor.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxErrors {
+    /// The transmit FIFO ran empty while the frame was being transmitted,
+    /// because the DMA could not supply the data in time.
+    pub underflow: bool,
+    /// The transmission was deferred for more than 24 288 bit times.
+    pub excessive_deferral: bool,
+    /// The transmission was aborted after 16 successive collisions,
+    /// or after a single collision if retries are disabled.
+    pub excessive_collisions: bool,
+    /// A collision occured after the collision window.
+    pub late_collision: bool,
+    /// The carrier signal from the PHY was not asserted.
+    pub no_carrier: bool,
+    /// The carrier was lost during the transmission.
+    pub loss_of_carrier: bool,
+    /// The transmitter was active for more than 2048 bytes.
+    pub jabber_timeout: bool,
+    /// The frame was flushed from the transmit FIFO by software.
+    pub frame_flushed: bool,
+    /// The checksum offload engine detected an error in the IP header.
+    pub ip_header_error: bool,
+    /// The checksum offload engine detected an error in the
+    /// TCP/UDP/ICMP payload.
+    pub payload_checksum_error: bool,
+}
+
+impl TxErrors {
+    fn from_tdes0(tdes0: u32) -> Self {
+        let is_set = |bit: u32| (tdes0 & bit) == bit;
+
+        Self {
+            underflow: is_set(TXDESC_0_UF),
+            excessive_deferral: is_set(TXDESC_0_ED),
+            excessive_collisions: is_set(TXDESC_0_EC),
+            late_collision: is_set(TXDESC_0_LCO),
+            no_carrier: is_set(TXDESC_0_NC),
+            loss_of_carrier: is_set(TXDESC_0_LCA),
+            jabber_timeout: is_set(TXDESC_0_JT),
+            frame_flushed: is_set(TXDESC_0_FF),
+            ip_header_error: is_set(TXDESC_0_IHE),
+            payload_checksum_error: is_set(TXDESC_0_IPE),
+        }
+    }
+}
+
+/// An identifier that can be attached to a packet when sending it,
+/// so that information about the packet can be retrieved after
+/// it has been transmitted.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketId(pub u32);
+
+impl From<u32> for PacketId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
 /// A TX DMA Ring Descriptor
 #[repr(C)]
 #[derive(Clone)]
 pub struct TxDescriptor {
     desc: Descriptor,
+    packet_id: Option<PacketId>,
 }
 
 impl Default for TxDescriptor {
@@ -56,6 +177,7 @@ impl TxDescriptor {
     pub const fn new() -> Self {
         Self {
             desc: Descriptor::new(),
+            packet_id: None,
         }
     }
 
@@ -81,11 +203,21 @@ impl TxDescriptor {
         atomic::fence(Ordering::SeqCst);
     }
 
-    #[allow(unused)]
     fn has_error(&self) -> bool {
         (self.desc.read(0) & TXDESC_0_ES) == TXDESC_0_ES
     }
 
+    /// The status of the packet in this descriptor.
+    fn status(&self) -> TxStatus {
+        if self.is_owned() {
+            TxStatus::Pending
+        } else if self.has_error() {
+            TxStatus::Failed(TxErrors::from_tdes0(self.desc.read(0)))
+        } else {
+            TxStatus::Transmitted
+        }
+    }
+
     fn set_buffer1(&mut self, buffer: *const u8) {
         unsafe {
             self.desc.write(2, buffer as u32);
@@ -112,25 +244,63 @@ impl TxDescriptor {
             self.desc.modify(0, |w| w | TXDESC_0_TER);
         }
     }
+
+    /// Attach `packet_id` to the packet in this descriptor.
+    ///
+    /// If the `ptp` feature is enabled, a transmit timestamp is
+    /// captured for packets that have an ID.
+    fn set_packet_id(&mut self, packet_id: Option<PacketId>) {
+        #[cfg(feature = "ptp")]
+        unsafe {
+            if packet_id.is_some() {
+                self.desc.modify(0, |w| w | TXDESC_0_TTSE);
+            } else {
+                self.desc.modify(0, |w| w & !TXDESC_0_TTSE);
+            }
+        }
+
+        self.packet_id = packet_id;
+    }
+
+    /// The timestamp that was captured when the packet in this
+    /// descriptor was transmitted, if any.
+    #[cfg(feature = "ptp")]
+    fn timestamp(&self) -> Option<Timestamp> {
+        let tdes0 = self.desc.read(0);
+
+        if (tdes0 & TXDESC_0_OWN) == 0 && (tdes0 & TXDESC_0_TTSS) == TXDESC_0_TTSS {
+            // TDES7 contains the seconds, TDES6 the subseconds.
+            Some(Timestamp::from_parts(self.desc.read(7), self.desc.read(6)))
+        } else {
+            None
+        }
+    }
+
+    /// Enable or disable insertion of the IPv4 header and
+    /// TCP/UDP/ICMP payload checksums (including the pseudo-header)
+    fn set_checksum_insertion(&mut self, enable: bool) {
+        unsafe {
+            if enable {
+                self.desc.modify(0, |w| w | TXDESC_0_CIC0 | TXDESC_0_CIC1);
+            } else {
+                self.desc
+                    .modify(0, |w| w & !(TXDESC_0_CIC0 | TXDESC_0_CIC1));
+            }
+        }
+    }
 }
 
 /// A TX DMA Ring Descriptor entry
-pub type TxRingEntry = RingEntry<TxDescriptor>;
+pub type TxRingEntry<const N: usize = DEFAULT_BUFFER_SIZE> = RingEntry<TxDescriptor, N>;
 
 impl RingDescriptor for TxDescriptor {
     fn setup(&mut self, buffer: *const u8, _len: usize, next: Option<&Self>) {
         // Defer this initialization to this function, so we can have `RingEntry` on bss.
         unsafe {
-            self.desc.write(
-                0,
-                TXDESC_0_TCH
-                    | TXDESC_0_IC
-                    | TXDESC_0_FS
-                    | TXDESC_0_LS
-                    | TXDESC_0_CIC0
-                    | TXDESC_0_CIC1,
-            );
+            self.desc
+                .write(0, TXDESC_0_TCH | TXDESC_0_IC | TXDESC_0_FS | TXDESC_0_LS);
         }
+        self.packet_id = None;
         self.set_buffer1(buffer);
         match next {
             Some(next) => self.set_buffer2(&next.desc as *const Descriptor as *const u8),
@@ -143,12 +313,17 @@ impl RingDescriptor for TxDescriptor {
     }
 }
 
-impl TxRingEntry {
-    fn prepare_packet(&mut self, length: usize) -> Option<TxPacket> {
+impl<const N: usize> TxRingEntry<N> {
+    fn prepare_packet(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+    ) -> Option<TxPacket<N>> {
         assert!(length <= self.as_slice().len());
 
         if !self.desc().is_owned() {
             self.desc_mut().set_buffer1_len(length);
+            self.desc_mut().set_packet_id(packet_id);
             Some(TxPacket {
                 entry: self,
                 length,
@@ -159,12 +334,12 @@ impl TxRingEntry {
     }
 }
 
-pub struct TxPacket<'a> {
-    entry: &'a mut TxRingEntry,
+pub struct TxPacket<'a, const N: usize> {
+    entry: &'a mut TxRingEntry<N>,
     length: usize,
 }
 
-impl<'a> Deref for TxPacket<'a> {
+impl<'a, const N: usize> Deref for TxPacket<'a, N> {
     type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
@@ -172,13 +347,13 @@ impl<'a> Deref for TxPacket<'a> {
     }
 }
 
-impl<'a> DerefMut for TxPacket<'a> {
+impl<'a, const N: usize> DerefMut for TxPacket<'a, N> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.entry.as_mut_slice()[0..self.length]
     }
 }
 
-impl<'a> TxPacket<'a> {
+impl<'a, const N: usize> TxPacket<'a, N> {
     // Pass to DMA engine
     pub fn send(self) {
         self.entry.desc_mut().set_owned();
@@ -186,27 +361,31 @@ impl<'a> TxPacket<'a> {
 }
 
 /// Tx DMA state
-pub struct TxRing<'a> {
-    entries: &'a mut [TxRingEntry],
+pub struct TxRing<'a, const N: usize> {
+    entries: &'a mut [TxRingEntry<N>],
     next_entry: usize,
+    checksum_insertion: bool,
+    statistics: TxStatistics,
 }
 
-impl<'a> TxRing<'a> {
+impl<'a, const N: usize> TxRing<'a, N> {
     /// Allocate
     ///
     /// `start()` will be needed before `send()`
-    pub fn new(entries: &'a mut [TxRingEntry]) -> Self {
+    pub fn new(entries: &'a mut [TxRingEntry<N>], checksum_insertion: bool) -> Self {
         TxRing {
             entries,
             next_entry: 0,
+            checksum_insertion,
+            statistics: TxStatistics::default(),
         }
     }
 
     /// Start the Tx DMA engine
-    pub fn start(&mut self, eth_dma: &ETHERNET_DMA) {
+    pub fn start(&mut self, eth_dma: &impl DmaRegisters) {
         // Setup ring
         {
-            let mut previous: Option<&mut TxRingEntry> = None;
+            let mut previous: Option<&mut TxRingEntry<N>> = None;
             for entry in self.entries.iter_mut() {
                 if let Some(prev_entry) = &mut previous {
                     prev_entry.setup(Some(entry));
@@ -218,12 +397,16 @@ impl<'a> TxRing<'a> {
             }
         }
 
+        for entry in self.entries.iter_mut() {
+            entry
+                .desc_mut()
+                .set_checksum_insertion(self.checksum_insertion);
+        }
+        self.next_entry = 0;
+
         let ring_ptr = self.entries[0].desc() as *const TxDescriptor;
         // Register TxDescriptor
-        eth_dma
-            .dmatdlar
-            // Note: unsafe block required for `stm32f107`.
-            .write(|w| unsafe { w.stl().bits(ring_ptr as u32) });
+        eth_dma.set_tx_descriptor_list(ring_ptr);
 
         // "Preceding reads and writes cannot be moved past subsequent writes."
         #[cfg(feature = "fence")]
@@ -233,54 +416,112 @@ impl<'a> TxRing<'a> {
         // volatiles
 
         // Start transmission
-        eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+        eth_dma.start_tx();
     }
 
     pub fn send<F: FnOnce(&mut [u8]) -> R, R>(
         &mut self,
         length: usize,
+        packet_id: Option<PacketId>,
         f: F,
     ) -> Result<R, TxError> {
         let entries_len = self.entries.len();
 
-        match self.entries[self.next_entry].prepare_packet(length) {
+        match self.entries[self.next_entry].prepare_packet(length, packet_id) {
             Some(mut pkt) => {
                 let r = f(pkt.deref_mut());
                 pkt.send();
 
+                self.statistics.packets += 1;
+                self.statistics.bytes += length as u64;
+                self.update_high_water();
+
                 self.next_entry += 1;
                 if self.next_entry >= entries_len {
                     self.next_entry = 0;
                 }
                 Ok(r)
             }
-            None => Err(TxError::WouldBlock),
+            None => {
+                self.statistics.would_block += 1;
+                Err(TxError::WouldBlock)
+            }
         }
     }
 
+    /// Count the descriptors that are waiting to be transmitted, up to
+    /// and including the one at `next_entry`.
+    fn update_high_water(&mut self) {
+        let entries_len = self.entries.len();
+
+        let pending = (0..entries_len)
+            .take_while(|i| {
+                let entry = (self.next_entry + entries_len - i) % entries_len;
+                self.entries[entry].desc().is_owned()
+            })
+            .count();
+
+        let high_water = &mut self.statistics.ring_high_water;
+        *high_water = (*high_water).max(pending);
+    }
+
+    /// The statistics about transmitted packets.
+    pub fn statistics(&self) -> TxStatistics {
+        self.statistics
+    }
+
+    /// Reset the statistics about transmitted packets.
+    pub fn reset_statistics(&mut self) {
+        self.statistics = TxStatistics::default();
+    }
+
+    /// Whether checksums are inserted into transmitted packets.
+    pub fn checksum_insertion(&self) -> bool {
+        self.checksum_insertion
+    }
+
+    /// Whether the next entry in the ring is available for sending a packet.
+    pub fn next_entry_available(&self) -> bool {
+        !self.entries[self.next_entry].desc().is_owned()
+    }
+
+    /// The status of the packet with ID `packet_id`, or `None`
+    /// if no packet with this ID is in the ring.
+    pub fn status(&self, packet_id: PacketId) -> Option<TxStatus> {
+        self.entries
+            .iter()
+            .map(|entry| entry.desc())
+            .find(|desc| desc.packet_id == Some(packet_id))
+            .map(|desc| desc.status())
+    }
+
+    /// The timestamp that was captured when the packet with
+    /// ID `packet_id` was transmitted.
+    ///
+    /// Returns `None` if no packet with this ID is in the ring, if the
+    /// packet has not been transmitted yet, or if no timestamp was captured.
+    #[cfg(feature = "ptp")]
+    pub fn timestamp(&self, packet_id: PacketId) -> Option<Timestamp> {
+        self.entries
+            .iter()
+            .map(|entry| entry.desc())
+            .find(|desc| desc.packet_id == Some(packet_id))
+            .and_then(|desc| desc.timestamp())
+    }
+
     /// Demand that the DMA engine polls the current `TxDescriptor`
     /// (when we just transferred ownership to the hardware).
-    pub fn demand_poll(&self, eth_dma: &ETHERNET_DMA) {
-        eth_dma.dmatpdr.write(|w| {
-            #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
-            {
-                w.tpd().poll()
-            }
-            #[cfg(feature = "stm32f1xx-hal")]
-            unsafe {
-                // TODO: There is no nice `poll` method for `stm32f107`?
-                w.tpd().bits(0)
-            }
-        });
+    pub fn demand_poll(&self, eth_dma: &impl DmaRegisters) {
+        eth_dma.demand_tx_poll();
     }
 
     /// Is the Tx DMA engine running?
-    pub fn is_running(&self, eth_dma: &ETHERNET_DMA) -> bool {
+    pub fn is_running(&self, eth_dma: &impl DmaRegisters) -> bool {
         self.running_state(eth_dma).is_running()
     }
 
-    fn running_state(&self, eth_dma: &ETHERNET_DMA) -> RunningState {
-        match eth_dma.dmasr.read().tps().bits() {
+    fn running_state(&self, eth_dma: &impl DmaRegisters) -> RunningState {
+        match eth_dma.tx_process_state() {
             // Reset or Stop Transmit Command issued
             0b000 => RunningState::Stopped,
             // Fetching transmit transfer descriptor
@@ -318,3 +559,140 @@ impl RunningState {
         *self == RunningState::Running
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::sim::SimDma;
+
+    const N: usize = 128;
+
+    fn send(ring: &mut TxRing<N>, data: &[u8], packet_id: Option<PacketId>) -> Result<(), TxError> {
+        ring.send(data.len(), packet_id, |buffer| buffer.copy_from_slice(data))
+    }
+
+    fn assert_transmitted(dma: &SimDma, expected: &[u8]) {
+        let mut buffer = [0; N];
+        let len = dma
+            .transmit(&mut buffer)
+            .expect("a packet should be pending");
+        assert_eq!(&buffer[..len], expected);
+    }
+
+    #[test]
+    fn send_and_transmit() {
+        let mut entries = [TxRingEntry::<N>::INIT; 4];
+        let mut ring = TxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        assert_eq!(dma.transmit(&mut [0; N]), None);
+
+        send(&mut ring, b"first packet", None).unwrap();
+        ring.demand_poll(&dma);
+        assert_eq!(dma.tx_polls(), 1);
+        assert!(ring.is_running(&dma));
+
+        assert_transmitted(&dma, b"first packet");
+        assert_eq!(dma.transmit(&mut [0; N]), None);
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut entries = [TxRingEntry::<N>::INIT; 3];
+        let mut ring = TxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        for i in 0..10u8 {
+            let data = [i; 60];
+            send(&mut ring, &data, None).unwrap();
+            assert_transmitted(&dma, &data);
+        }
+
+        let statistics = ring.statistics();
+        assert_eq!(statistics.packets, 10);
+        assert_eq!(statistics.bytes, 600);
+    }
+
+    #[test]
+    fn full_ring_would_block() {
+        let mut entries = [TxRingEntry::<N>::INIT; 2];
+        let mut ring = TxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        send(&mut ring, &[1; 60], None).unwrap();
+        send(&mut ring, &[2; 60], None).unwrap();
+        assert!(!ring.next_entry_available());
+        assert_eq!(send(&mut ring, &[3; 60], None), Err(TxError::WouldBlock));
+
+        // The DMA passes the first descriptor back.
+        assert_transmitted(&dma, &[1; 60]);
+        assert!(ring.next_entry_available());
+        send(&mut ring, &[4; 60], None).unwrap();
+
+        assert_transmitted(&dma, &[2; 60]);
+        assert_transmitted(&dma, &[4; 60]);
+
+        let statistics = ring.statistics();
+        assert_eq!(statistics.would_block, 1);
+        assert_eq!(statistics.ring_high_water, 2);
+    }
+
+    #[test]
+    fn packet_status() {
+        let mut entries = [TxRingEntry::<N>::INIT; 2];
+        let mut ring = TxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        send(&mut ring, &[1; 60], Some(PacketId(1))).unwrap();
+        send(&mut ring, &[2; 60], Some(PacketId(2))).unwrap();
+        assert_eq!(ring.status(PacketId(1)), Some(TxStatus::Pending));
+        assert_eq!(ring.status(PacketId(3)), None);
+
+        assert_transmitted(&dma, &[1; 60]);
+        assert_eq!(ring.status(PacketId(1)), Some(TxStatus::Transmitted));
+        assert_eq!(ring.status(PacketId(2)), Some(TxStatus::Pending));
+
+        dma.transmit_with_status(&mut [0; N], TXDESC_0_UF);
+        assert_eq!(
+            ring.status(PacketId(2)),
+            Some(TxStatus::Failed(TxErrors {
+                underflow: true,
+                ..Default::default()
+            }))
+        );
+
+        // Reusing the descriptors replaces the IDs and the previous status.
+        send(&mut ring, &[3; 60], None).unwrap();
+        send(&mut ring, &[4; 60], Some(PacketId(4))).unwrap();
+        assert_eq!(ring.status(PacketId(2)), None);
+        assert_eq!(ring.status(PacketId(4)), Some(TxStatus::Pending));
+
+        assert_transmitted(&dma, &[3; 60]);
+        assert_transmitted(&dma, &[4; 60]);
+        assert_eq!(ring.status(PacketId(4)), Some(TxStatus::Transmitted));
+    }
+
+    #[test]
+    fn restart_drops_pending_packets() {
+        let mut entries = [TxRingEntry::<N>::INIT; 4];
+        let mut ring = TxRing::new(&mut entries, false);
+        let dma = SimDma::new();
+        ring.start(&dma);
+
+        send(&mut ring, &[1; 60], None).unwrap();
+        assert_transmitted(&dma, &[1; 60]);
+        send(&mut ring, &[2; 60], Some(PacketId(2))).unwrap();
+
+        ring.start(&dma);
+        assert_eq!(ring.status(PacketId(2)), None);
+        assert_eq!(dma.transmit(&mut [0; N]), None);
+
+        // Both the ring and the DMA continue at the first descriptor.
+        send(&mut ring, &[3; 60], None).unwrap();
+        assert_transmitted(&dma, &[3; 60]);
+    }
+}